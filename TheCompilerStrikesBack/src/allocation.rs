@@ -0,0 +1,138 @@
+use common_game::components::planet::PlanetState;
+use std::sync::{Arc, Mutex};
+
+/*
+   Choice of the charged cell spent by the AI.
+
+   Every generation, combination and rocket asks the allocator for the index of a charged
+   cell instead of taking the first one, and the cells actually discharged are counted per
+   index, split between the ones that fed a rocket and the ones that fed an explorer.
+   A policy can also refuse a cell to explorers, which the AI treats like no cell being charged.
+*/
+
+/// Which charged cell is spent first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CellPolicy {
+    /// The charged cell with the lowest index
+    #[default]
+    FirstFit,
+    /// The first charged cell after the one spent last, wrapping around
+    RoundRobin,
+    /// Explorers spend the charged cell with the lowest index, but not the last one left
+    /// while the planet can build a rocket and has none, rockets the one with the highest
+    KeepLastForRocket,
+}
+
+/// What a cell is spent on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CellUse {
+    Rocket,
+    Explorer,
+}
+
+/// Times a cell was spent
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CellUsage {
+    pub rockets: u64,
+    pub explorers: u64,
+}
+
+/// Usage of every cell of a planet, counted as the cells are spent
+#[derive(Debug, Clone, Default)]
+pub struct CellStatistics {
+    usage: Arc<Mutex<Vec<CellUsage>>>,
+}
+
+impl CellStatistics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Usage by cell index, up to the last cell spent
+    pub fn usage(&self) -> Vec<CellUsage> {
+        self.usage.lock().unwrap().clone()
+    }
+
+    /// Usage of the cell at `index`
+    pub fn cell(&self, index: usize) -> CellUsage {
+        self.usage
+            .lock()
+            .unwrap()
+            .get(index)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    fn record(&self, index: usize, purpose: CellUse) {
+        let mut usage = self.usage.lock().unwrap();
+        if usage.len() <= index {
+            usage.resize(index + 1, CellUsage::default());
+        }
+        match purpose {
+            CellUse::Rocket => usage[index].rockets += 1,
+            CellUse::Explorer => usage[index].explorers += 1,
+        }
+    }
+}
+
+pub(crate) struct CellAllocator {
+    pub(crate) policy: CellPolicy,
+    pub(crate) statistics: CellStatistics,
+    /// Index the round robin starts looking from
+    next: usize,
+}
+
+impl CellAllocator {
+    pub(crate) fn new(policy: CellPolicy, statistics: CellStatistics) -> Self {
+        Self {
+            policy,
+            statistics,
+            next: 0,
+        }
+    }
+
+    /// Index of the charged cell to spend, if the policy allows spending one
+    pub(crate) fn pick(&self, state: &PlanetState, purpose: CellUse) -> Option<usize> {
+        let mut charged = state
+            .cells_iter()
+            .enumerate()
+            .filter(|(_, cell)| cell.is_charged())
+            .map(|(index, _)| index);
+        match (self.policy, purpose) {
+            (CellPolicy::FirstFit, _) => charged.next(),
+            (CellPolicy::KeepLastForRocket, CellUse::Explorer) if self.keeps_last(state) => {
+                let first = charged.next();
+                charged.next().and(first)
+            }
+            (CellPolicy::KeepLastForRocket, CellUse::Explorer) => charged.next(),
+            (CellPolicy::KeepLastForRocket, CellUse::Rocket) => charged.next_back(),
+            (CellPolicy::RoundRobin, _) => {
+                let charged: Vec<usize> = charged.collect();
+                charged
+                    .iter()
+                    .find(|index| **index >= self.next)
+                    .or(charged.first())
+                    .copied()
+            }
+        }
+    }
+
+    /// Whether the last charged cell is kept from explorers for the next rocket
+    pub(crate) fn keeps_last(&self, state: &PlanetState) -> bool {
+        self.policy == CellPolicy::KeepLastForRocket
+            && state.can_have_rocket()
+            && !state.has_rocket()
+    }
+
+    /// Charged cells explorers can spend
+    pub(crate) fn explorer_cells(&self, state: &PlanetState) -> usize {
+        let charged = state.cells_iter().filter(|cell| cell.is_charged()).count();
+        charged.saturating_sub(usize::from(self.keeps_last(state)))
+    }
+
+    /// Count a cell that was spent
+    pub(crate) fn record(&mut self, index: usize, purpose: CellUse) {
+        self.next = index + 1;
+        self.statistics.record(index, purpose);
+    }
+}
@@ -0,0 +1,70 @@
+/*
+   Offline galaxy simulation, used to compare the planet strategies.
+
+   usage: galaxy_sim [--seed N] [--ticks N] [--planets N] [--strategy defensive|economic|balanced|adaptive]
+                     [--sunray P] [--asteroid P] [--explorer P]
+*/
+
+use std::env;
+use std::process::ExitCode;
+use the_compiler_strikes_back::galaxy::{GalaxyConfig, simulate};
+use the_compiler_strikes_back::strategy;
+
+fn parse_args() -> Result<GalaxyConfig, String> {
+    let mut config = GalaxyConfig::default();
+    let mut args = env::args().skip(1);
+
+    while let Some(flag) = args.next() {
+        let value = args
+            .next()
+            .ok_or_else(|| format!("missing value for {flag}"))?;
+        let invalid = |_| format!("invalid value for {flag}: {value}");
+        match flag.as_str() {
+            "--seed" => config.seed = value.parse().map_err(invalid)?,
+            "--ticks" => config.ticks = value.parse().map_err(invalid)?,
+            "--planets" => config.planets = value.parse().map_err(invalid)?,
+            "--strategy" => {
+                if strategy::by_name(&value).is_none() {
+                    return Err(format!("unknown strategy {value}"));
+                }
+                config.strategy = value;
+            }
+            "--sunray" => config.sunray_probability = parse_probability(&flag, &value)?,
+            "--asteroid" => config.asteroid_probability = parse_probability(&flag, &value)?,
+            "--explorer" => config.explorer_probability = parse_probability(&flag, &value)?,
+            _ => return Err(format!("unknown option {flag}")),
+        }
+    }
+    Ok(config)
+}
+
+fn parse_probability(flag: &str, value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(p) if (0.0..=1.0).contains(&p) => Ok(p),
+        _ => Err(format!(
+            "{flag} expects a probability between 0 and 1, got {value}"
+        )),
+    }
+}
+
+fn main() -> ExitCode {
+    let config = match parse_args() {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("{err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match simulate(&config) {
+        Ok(report) => {
+            println!("{config:?}");
+            println!("{report}");
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("simulation failed: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
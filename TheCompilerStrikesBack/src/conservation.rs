@@ -0,0 +1,173 @@
+use crate::planner::recipe_inputs;
+use common_game::components::resource::{
+    ComplexResourceRequest, ComplexResourceType, ResourceType,
+};
+use common_game::protocols::planet_explorer::PlanetToExplorer;
+use std::sync::{Arc, Mutex};
+
+/*
+   Conservation audit of the resources exchanged with explorers.
+
+   The two inputs of a combination request enter the planet and must leave it exactly once:
+   either consumed into the requested resource or handed back inside the `Err` tuple.
+   Every combination response is compared with the inputs of its request, and a response
+   that loses, duplicates or swaps a resource is reported as a violation.
+
+   The inputs are counted when the request enters the planet, which hands out an
+   AuditTicket that travels with the request, parked or not, and is settled by its response.
+   The audit only runs in debug builds: in release builds the tickets count nothing.
+*/
+
+/// Combination response that didn't account for the inputs of its request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConservationViolation {
+    pub explorer_id: u32,
+    /// Resource the explorer asked for
+    pub requested: ComplexResourceType,
+    /// Inputs carried by the request
+    pub received: [ResourceType; 2],
+    /// Resources found in the response
+    pub sent: Vec<ResourceType>,
+}
+
+/// Resources counted by a [`ConservationAudit`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConservationReport {
+    /// Inputs received with combination requests
+    pub received: u64,
+    /// Inputs turned into a complex resource
+    pub consumed: u64,
+    /// Inputs handed back by failed combinations
+    pub returned: u64,
+    /// Complex resources produced by combinations
+    pub produced: u64,
+    pub violations: Vec<ConservationViolation>,
+}
+
+impl ConservationReport {
+    /// Inputs still held by the planet, parked with a queued request
+    pub fn held(&self) -> u64 {
+        self.received.saturating_sub(self.consumed + self.returned)
+    }
+
+    /// Whether every answered request accounted for its inputs
+    pub fn is_balanced(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+/// Conservation auditor of the combinations served by one or more planets.
+#[derive(Debug, Clone, Default)]
+pub struct ConservationAudit {
+    report: Arc<Mutex<ConservationReport>>,
+}
+
+impl ConservationAudit {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn report(&self) -> ConservationReport {
+        self.report.lock().unwrap().clone()
+    }
+
+    /// Count the inputs of a combination request entering the planet
+    pub(crate) fn receive(
+        audit: Option<&ConservationAudit>,
+        explorer_id: u32,
+        msg: &ComplexResourceRequest,
+    ) -> AuditTicket {
+        let audit = audit.filter(|_| cfg!(debug_assertions)).cloned();
+        if let Some(audit) = audit.as_ref() {
+            audit.report.lock().unwrap().received += 2;
+        }
+        let (requested, received) = combination_types(msg);
+        AuditTicket {
+            audit,
+            explorer_id,
+            requested,
+            received,
+        }
+    }
+
+    /// Count a combination response, returning the violation it carries, if any
+    fn record_combination(
+        &self,
+        explorer_id: u32,
+        requested: ComplexResourceType,
+        received: [ResourceType; 2],
+        response: &PlanetToExplorer,
+    ) -> Option<ConservationViolation> {
+        let mut report = self.report.lock().unwrap();
+        let (sent, balanced) = match response {
+            PlanetToExplorer::CombineResourceResponse {
+                complex_response: Ok(complex),
+            } => {
+                report.consumed += 2;
+                report.produced += 1;
+                let produced = complex.get_type();
+                (vec![ResourceType::Complex(produced)], produced == requested)
+            }
+            PlanetToExplorer::CombineResourceResponse {
+                complex_response: Err((_, r1, r2)),
+            } => {
+                report.returned += 2;
+                let sent = [r1.get_type(), r2.get_type()];
+                let balanced = sent == received || sent == [received[1], received[0]];
+                (sent.to_vec(), balanced)
+            }
+            _ => (Vec::new(), false),
+        };
+        if balanced {
+            return None;
+        }
+        let violation = ConservationViolation {
+            explorer_id,
+            requested,
+            received,
+            sent,
+        };
+        report.violations.push(violation.clone());
+        Some(violation)
+    }
+
+    pub fn clear(&self) {
+        *self.report.lock().unwrap() = ConservationReport::default();
+    }
+}
+
+/// Inputs of a combination request counted by the audit, until the response hands them out
+pub(crate) struct AuditTicket {
+    audit: Option<ConservationAudit>,
+    explorer_id: u32,
+    requested: ComplexResourceType,
+    received: [ResourceType; 2],
+}
+
+impl AuditTicket {
+    /// Count the response to the request, returning the violation it carries, if any
+    pub(crate) fn settle(self, response: &PlanetToExplorer) -> Option<ConservationViolation> {
+        self.audit.as_ref()?.record_combination(
+            self.explorer_id,
+            self.requested,
+            self.received,
+            response,
+        )
+    }
+}
+
+/// Requested resource and input types of a combination request
+pub(crate) fn combination_types(
+    msg: &ComplexResourceRequest,
+) -> (ComplexResourceType, [ResourceType; 2]) {
+    let requested = match msg {
+        ComplexResourceRequest::Water(..) => ComplexResourceType::Water,
+        ComplexResourceRequest::Diamond(..) => ComplexResourceType::Diamond,
+        ComplexResourceRequest::Life(..) => ComplexResourceType::Life,
+        ComplexResourceRequest::Robot(..) => ComplexResourceType::Robot,
+        ComplexResourceRequest::Dolphin(..) => ComplexResourceType::Dolphin,
+        ComplexResourceRequest::AIPartner(..) => ComplexResourceType::AIPartner,
+    };
+    let (lhs, rhs) = recipe_inputs(requested);
+    (requested, [lhs, rhs])
+}
@@ -0,0 +1,142 @@
+use common_game::components::resource::{BasicResourceType, ComplexResourceType, ResourceType};
+use std::collections::{BTreeMap, HashMap};
+
+/*
+   Barter economy of our planet.
+
+   Every generated or combined resource costs the explorer its price in credit.
+   Explorers earn credit by delivering resources: the inputs consumed by a combination
+   are worth their price, so a combination only costs the difference between the product
+   and its inputs. Each explorer gets a welcome credit on its first visit and keeps its
+   account between visits.
+   Credit saturates at u32::MAX instead of overflowing.
+*/
+
+/// Price in credit of every resource, resources without a price cost `default_price`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PriceList {
+    pub default_price: u32,
+    basic: HashMap<BasicResourceType, u32>,
+    complex: HashMap<ComplexResourceType, u32>,
+}
+
+impl PriceList {
+    pub fn new(default_price: u32) -> Self {
+        Self {
+            default_price,
+            ..Self::default()
+        }
+    }
+
+    pub fn basic(mut self, resource: BasicResourceType, price: u32) -> Self {
+        self.basic.insert(resource, price);
+        self
+    }
+
+    pub fn complex(mut self, resource: ComplexResourceType, price: u32) -> Self {
+        self.complex.insert(resource, price);
+        self
+    }
+
+    pub fn price(&self, resource: ResourceType) -> u32 {
+        let price = match resource {
+            ResourceType::Basic(basic) => self.basic.get(&basic),
+            ResourceType::Complex(complex) => self.complex.get(&complex),
+        };
+        price.copied().unwrap_or(self.default_price)
+    }
+}
+
+/// Configuration of the barter economy
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EconomyConfig {
+    pub prices: PriceList,
+    /// Credit given to an explorer on its first visit
+    pub welcome_credit: u32,
+}
+
+/// Credit of an explorer
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Account {
+    pub balance: u32,
+    /// Credit earned by delivering resources
+    pub earned: u32,
+    /// Credit spent on generations and combinations
+    pub spent: u32,
+}
+
+pub(crate) struct Economy {
+    pub(crate) config: EconomyConfig,
+    ledger: BTreeMap<u32, Account>,
+}
+
+impl Economy {
+    pub(crate) fn new(config: EconomyConfig) -> Self {
+        Self {
+            config,
+            ledger: BTreeMap::new(),
+        }
+    }
+
+    /// Open the account of an explorer on its first visit
+    pub(crate) fn welcome(&mut self, explorer_id: u32) {
+        let welcome_credit = self.config.welcome_credit;
+        self.ledger.entry(explorer_id).or_insert(Account {
+            balance: welcome_credit,
+            ..Account::default()
+        });
+    }
+
+    pub(crate) fn account(&self, explorer_id: u32) -> Account {
+        self.ledger.get(&explorer_id).copied().unwrap_or_default()
+    }
+
+    /// Every account, by explorer id
+    pub(crate) fn ledger(&self) -> Vec<(u32, Account)> {
+        self.ledger.iter().map(|(id, a)| (*id, *a)).collect()
+    }
+
+    /// Credit an explorer would have after delivering `delivered` and buying `bought`,
+    /// if it can afford it
+    fn settle(
+        &self,
+        explorer_id: u32,
+        bought: ResourceType,
+        delivered: &[ResourceType],
+    ) -> Option<(u32, u32)> {
+        let prices = &self.config.prices;
+        let earned = delivered
+            .iter()
+            .fold(0u32, |sum, r| sum.saturating_add(prices.price(*r)));
+        let cost = prices.price(bought);
+        (self.account(explorer_id).balance.saturating_add(earned))
+            .checked_sub(cost)
+            .map(|_| (earned, cost))
+    }
+
+    /// Whether an explorer can buy a resource, delivering some others in exchange
+    pub(crate) fn can_afford(
+        &self,
+        explorer_id: u32,
+        bought: ResourceType,
+        delivered: &[ResourceType],
+    ) -> bool {
+        self.settle(explorer_id, bought, delivered).is_some()
+    }
+
+    /// Settle the purchase of a resource, delivering some others in exchange
+    pub(crate) fn charge(
+        &mut self,
+        explorer_id: u32,
+        bought: ResourceType,
+        delivered: &[ResourceType],
+    ) {
+        let Some((earned, cost)) = self.settle(explorer_id, bought, delivered) else {
+            return;
+        };
+        let account = self.ledger.entry(explorer_id).or_default();
+        account.earned = account.earned.saturating_add(earned);
+        account.spent = account.spent.saturating_add(cost);
+        account.balance = account.balance.saturating_add(earned) - cost;
+    }
+}
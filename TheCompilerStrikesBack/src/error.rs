@@ -0,0 +1,61 @@
+use std::fmt;
+
+/*
+   Errors raised while creating a planet.
+
+   `Planet::new` only reports a plain string, so the message is matched against
+   the checks it performs and wrapped in the corresponding variant.
+   The original message is always kept.
+*/
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PlanetCreationError {
+    /// No generation rule was given
+    EmptyGenerationRules(String),
+    /// More generation rules than the planet type allows
+    TooManyGenerationRules(String),
+    /// More combination rules than the planet type allows
+    TooManyCombinationRules(String),
+    /// Any other reason reported by `Planet::new`
+    Other(String),
+}
+
+impl PlanetCreationError {
+    /// The original message reported by `Planet::new`
+    pub fn reason(&self) -> &str {
+        match self {
+            PlanetCreationError::EmptyGenerationRules(reason)
+            | PlanetCreationError::TooManyGenerationRules(reason)
+            | PlanetCreationError::TooManyCombinationRules(reason)
+            | PlanetCreationError::Other(reason) => reason,
+        }
+    }
+}
+
+impl From<String> for PlanetCreationError {
+    fn from(reason: String) -> Self {
+        if reason.starts_with("gen_rules is empty") {
+            PlanetCreationError::EmptyGenerationRules(reason)
+        } else if reason.starts_with("Too many generation rules") {
+            PlanetCreationError::TooManyGenerationRules(reason)
+        } else if reason.starts_with("Too many combination rules") {
+            PlanetCreationError::TooManyCombinationRules(reason)
+        } else {
+            PlanetCreationError::Other(reason)
+        }
+    }
+}
+
+impl fmt::Display for PlanetCreationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let category = match self {
+            PlanetCreationError::EmptyGenerationRules(_) => "empty generation rules",
+            PlanetCreationError::TooManyGenerationRules(_) => "too many generation rules",
+            PlanetCreationError::TooManyCombinationRules(_) => "too many combination rules",
+            PlanetCreationError::Other(_) => "planet creation failed",
+        };
+        write!(f, "{category}: {}", self.reason())
+    }
+}
+
+impl std::error::Error for PlanetCreationError {}
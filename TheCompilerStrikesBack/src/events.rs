@@ -0,0 +1,218 @@
+use crate::economy::Account;
+use common_game::components::resource::{ComplexResourceType, ResourceType};
+use common_game::protocols::planet_explorer::ExplorerToPlanetKind;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Decision taken by the planet AI
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PlanetEvent {
+    /// A sunray charged an empty cell
+    CellCharged,
+    /// A sunray found every cell charged and was discarded
+    CellWasted {
+        /// Sunrays wasted since the planet was created
+        total_wasted: u32,
+    },
+    RocketBuilt,
+    RocketBuildFailed {
+        reason: String,
+        /// Failed builds since the planet was created
+        total_failed: u32,
+    },
+    /// The rocket was handed to the orchestrator to stop an asteroid
+    RocketLaunched,
+    RequestServed {
+        explorer_id: u32,
+        request: ExplorerToPlanetKind,
+    },
+    RequestDenied {
+        explorer_id: u32,
+        request: ExplorerToPlanetKind,
+        reason: String,
+    },
+    /// The request came from an explorer that isn't on the planet and was refused
+    RequestRejected {
+        explorer_id: u32,
+        request: ExplorerToPlanetKind,
+    },
+    /// The request was parked until a cell is charged
+    RequestQueued {
+        explorer_id: u32,
+        request: ExplorerToPlanetKind,
+    },
+    /// A charged cell is held for the explorer after it was offered one
+    CellReserved {
+        explorer_id: u32,
+    },
+    /// The explorer didn't use its reserved cell in time
+    ReservationExpired {
+        explorer_id: u32,
+    },
+    /// The reserved cell was spent on a rocket to survive an asteroid
+    ReservationDropped {
+        explorer_id: u32,
+    },
+    /// A deferred response couldn't be sent to the explorer
+    ResponseUndeliverable {
+        explorer_id: u32,
+    },
+    ExplorerArrived {
+        explorer_id: u32,
+    },
+    /// Summary of the visit of an explorer that left the planet
+    ExplorerLeft {
+        explorer_id: u32,
+        requests: u32,
+        resources_handed_out: u32,
+    },
+    /// A combination response lost, duplicated or swapped one of the inputs of its request
+    ResourcesNotConserved {
+        explorer_id: u32,
+        requested: ComplexResourceType,
+        /// Resources found in the response
+        sent: Vec<ResourceType>,
+    },
+    /// Credit of every explorer, reported with the internal state of the planet
+    LedgerReported {
+        /// Accounts by explorer id
+        accounts: Vec<(u32, Account)>,
+    },
+    /// The snapshot was written after the AI was stopped
+    SnapshotSaved,
+    /// The charged cells and the rocket of a snapshot were replayed
+    SnapshotRestored,
+    SnapshotFailed {
+        reason: String,
+    },
+}
+
+impl PlanetEvent {
+    /// Explorer the event is about, if any
+    pub fn explorer_id(&self) -> Option<u32> {
+        match self {
+            PlanetEvent::RequestServed { explorer_id, .. }
+            | PlanetEvent::RequestDenied { explorer_id, .. }
+            | PlanetEvent::RequestRejected { explorer_id, .. }
+            | PlanetEvent::RequestQueued { explorer_id, .. }
+            | PlanetEvent::CellReserved { explorer_id }
+            | PlanetEvent::ReservationExpired { explorer_id }
+            | PlanetEvent::ReservationDropped { explorer_id }
+            | PlanetEvent::ResponseUndeliverable { explorer_id }
+            | PlanetEvent::ExplorerArrived { explorer_id }
+            | PlanetEvent::ExplorerLeft { explorer_id, .. }
+            | PlanetEvent::ResourcesNotConserved { explorer_id, .. } => Some(*explorer_id),
+            PlanetEvent::CellCharged
+            | PlanetEvent::CellWasted { .. }
+            | PlanetEvent::RocketBuilt
+            | PlanetEvent::RocketBuildFailed { .. }
+            | PlanetEvent::RocketLaunched
+            | PlanetEvent::LedgerReported { .. }
+            | PlanetEvent::SnapshotSaved
+            | PlanetEvent::SnapshotRestored
+            | PlanetEvent::SnapshotFailed { .. } => None,
+        }
+    }
+
+    /// Short name of the event, used as the `event` entry of the log payload
+    pub fn name(&self) -> &'static str {
+        match self {
+            PlanetEvent::CellCharged => "cell charged",
+            PlanetEvent::CellWasted { .. } => "cell wasted",
+            PlanetEvent::RocketBuilt => "rocket built",
+            PlanetEvent::RocketBuildFailed { .. } => "rocket build failed",
+            PlanetEvent::RocketLaunched => "rocket launched",
+            PlanetEvent::RequestServed { .. } => "request served",
+            PlanetEvent::RequestDenied { .. } => "request denied",
+            PlanetEvent::RequestRejected { .. } => "request rejected",
+            PlanetEvent::RequestQueued { .. } => "request queued",
+            PlanetEvent::CellReserved { .. } => "cell reserved",
+            PlanetEvent::ReservationExpired { .. } => "reservation expired",
+            PlanetEvent::ReservationDropped { .. } => "reservation dropped",
+            PlanetEvent::ResponseUndeliverable { .. } => "response undeliverable",
+            PlanetEvent::ExplorerArrived { .. } => "explorer arrived",
+            PlanetEvent::ExplorerLeft { .. } => "explorer left",
+            PlanetEvent::ResourcesNotConserved { .. } => "resources not conserved",
+            PlanetEvent::LedgerReported { .. } => "ledger reported",
+            PlanetEvent::SnapshotSaved => "snapshot saved",
+            PlanetEvent::SnapshotRestored => "snapshot restored",
+            PlanetEvent::SnapshotFailed { .. } => "snapshot failed",
+        }
+    }
+}
+
+/// Event captured by an [`EventLog`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordedEvent {
+    pub timestamp_unix: u64,
+    pub planet_id: u32,
+    pub event: PlanetEvent,
+}
+
+/// In-memory ring buffer keeping the last `capacity` events of one or more planets.
+#[derive(Debug, Clone)]
+pub struct EventLog {
+    capacity: usize,
+    events: Arc<Mutex<VecDeque<RecordedEvent>>>,
+}
+
+impl EventLog {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            events: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+        }
+    }
+
+    pub fn record(&self, planet_id: u32, event: PlanetEvent) {
+        if self.capacity == 0 {
+            return;
+        }
+        let timestamp_unix = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        let mut events = self.events.lock().unwrap();
+        if events.len() == self.capacity {
+            events.pop_front();
+        }
+        events.push_back(RecordedEvent {
+            timestamp_unix,
+            planet_id,
+            event,
+        });
+    }
+
+    /// Captured events, oldest first
+    pub fn events(&self) -> Vec<RecordedEvent> {
+        self.events.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Captured events matching a predicate, oldest first
+    pub fn query(&self, predicate: impl Fn(&RecordedEvent) -> bool) -> Vec<RecordedEvent> {
+        self.events
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|e| predicate(e))
+            .cloned()
+            .collect()
+    }
+
+    /// Captured events about an explorer, oldest first
+    pub fn for_explorer(&self, explorer_id: u32) -> Vec<RecordedEvent> {
+        self.query(|e| e.event.explorer_id() == Some(explorer_id))
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&self) {
+        self.events.lock().unwrap().clear();
+    }
+}
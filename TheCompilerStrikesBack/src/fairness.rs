@@ -0,0 +1,91 @@
+use std::collections::{HashMap, HashSet};
+
+/*
+   Fairness between the explorers visiting our planet.
+
+   Every cell consumed by a generation or a combination is charged to the explorer
+   that asked for it, until the explorer leaves the planet.
+
+   Under round-robin an explorer that found no cell for itself waits for one until it is
+   served or leaves, and the explorers that consumed more cells than it give way:
+   they leave one charged cell for each waiting explorer behind them, and start waiting too.
+*/
+
+/// How charged cells are shared between explorers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FairnessPolicy {
+    /// Explorers can consume every charged cell
+    #[default]
+    Unlimited,
+    /// Each explorer can consume at most `cells_per_visit` cells before leaving the planet
+    Quota { cells_per_visit: u32 },
+    /// Explorers waiting for a cell are served before the ones that consumed more cells,
+    /// so explorers competing for the cells are served in turn
+    RoundRobin,
+}
+
+pub(crate) struct Fairness {
+    pub(crate) policy: FairnessPolicy,
+    consumed: HashMap<u32, u32>,
+    waiting: HashSet<u32>,
+}
+
+impl Fairness {
+    pub(crate) fn new(policy: FairnessPolicy) -> Self {
+        Self {
+            policy,
+            consumed: HashMap::new(),
+            waiting: HashSet::new(),
+        }
+    }
+
+    /// Cells consumed by an explorer during its current visit
+    pub(crate) fn consumed(&self, explorer_id: u32) -> u32 {
+        self.consumed.get(&explorer_id).copied().unwrap_or(0)
+    }
+
+    /// Whether an explorer can consume another cell
+    pub(crate) fn allows(&self, explorer_id: u32) -> bool {
+        match self.policy {
+            FairnessPolicy::Quota { cells_per_visit } => {
+                self.consumed(explorer_id) < cells_per_visit
+            }
+            FairnessPolicy::Unlimited | FairnessPolicy::RoundRobin => true,
+        }
+    }
+
+    /// Whether an explorer has to leave the `charged` cells it could spend to the waiting
+    /// explorers behind it: the ones that consumed fewer cells, or as many if it isn't waiting itself
+    pub(crate) fn gives_way(&self, explorer_id: u32, charged: usize) -> bool {
+        if self.policy != FairnessPolicy::RoundRobin {
+            return false;
+        }
+        let consumed = self.consumed(explorer_id);
+        let waiting = self.waiting.contains(&explorer_id);
+        let behind = self
+            .waiting
+            .iter()
+            .filter(|&&other| other != explorer_id)
+            .map(|&other| self.consumed(other))
+            .filter(|&other| other < consumed || (other == consumed && !waiting))
+            .count();
+        behind > 0 && charged <= behind
+    }
+
+    /// Mark an explorer that found no cell for itself as waiting for one
+    pub(crate) fn wait(&mut self, explorer_id: u32) {
+        if self.policy == FairnessPolicy::RoundRobin {
+            self.waiting.insert(explorer_id);
+        }
+    }
+
+    pub(crate) fn record(&mut self, explorer_id: u32) {
+        *self.consumed.entry(explorer_id).or_insert(0) += 1;
+        self.waiting.remove(&explorer_id);
+    }
+
+    pub(crate) fn reset(&mut self, explorer_id: u32) {
+        self.consumed.remove(&explorer_id);
+        self.waiting.remove(&explorer_id);
+    }
+}
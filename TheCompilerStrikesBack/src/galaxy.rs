@@ -0,0 +1,209 @@
+use crate::error::PlanetCreationError;
+use crate::harness::{ExplorerHandle, HarnessError, PlanetHarness};
+use crate::planet::PlanetBuilder;
+use crate::strategy;
+use common_game::components::resource::BasicResourceType;
+use common_game::protocols::planet_explorer::{ExplorerToPlanet, PlanetToExplorer};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::fmt;
+
+/*
+   Discrete-event simulation of a small galaxy made of our planets.
+
+   Time advances in ticks. At every tick each planet still alive may receive, in order,
+   a sunray, an asteroid and a generation request from the explorer visiting it,
+   each with its own probability drawn from a seeded RNG.
+   Every event waits for the planet response before the next one is sent,
+   so the same configuration always produces the same report.
+*/
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GalaxyConfig {
+    pub seed: u64,
+    pub ticks: u32,
+    pub planets: u32,
+    /// Name of the planet strategy, see [`strategy::by_name`]
+    pub strategy: String,
+    pub sunray_probability: f64,
+    pub asteroid_probability: f64,
+    pub explorer_probability: f64,
+}
+
+impl Default for GalaxyConfig {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            ticks: 100,
+            planets: 1,
+            strategy: "defensive".to_string(),
+            sunray_probability: 0.5,
+            asteroid_probability: 0.05,
+            explorer_probability: 0.3,
+        }
+    }
+}
+
+/// What happened to a single planet
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PlanetReport {
+    pub planet_id: u32,
+    /// Tick of the asteroid that destroyed the planet
+    pub destroyed_at: Option<u32>,
+    pub sunrays: u32,
+    pub asteroids: u32,
+    pub rockets_built: u32,
+    /// Cells spent to serve the explorer generation requests
+    pub cells_to_explorers: u32,
+    pub explorer_requests: u32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GalaxyReport {
+    pub planets: Vec<PlanetReport>,
+}
+
+impl GalaxyReport {
+    pub fn survival_rate(&self) -> f64 {
+        if self.planets.is_empty() {
+            return 0.0;
+        }
+        let alive = self
+            .planets
+            .iter()
+            .filter(|p| p.destroyed_at.is_none())
+            .count();
+        alive as f64 / self.planets.len() as f64
+    }
+
+    pub fn rockets_built(&self) -> u32 {
+        self.planets.iter().map(|p| p.rockets_built).sum()
+    }
+
+    pub fn cells_to_explorers(&self) -> u32 {
+        self.planets.iter().map(|p| p.cells_to_explorers).sum()
+    }
+}
+
+impl fmt::Display for GalaxyReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "planet  destroyed_at  sunrays  asteroids  rockets  explorer_cells/requests"
+        )?;
+        for p in &self.planets {
+            let destroyed_at = p
+                .destroyed_at
+                .map_or_else(|| "-".to_string(), |t| t.to_string());
+            writeln!(
+                f,
+                "{:>6}  {:>12}  {:>7}  {:>9}  {:>7}  {}/{}",
+                p.planet_id,
+                destroyed_at,
+                p.sunrays,
+                p.asteroids,
+                p.rockets_built,
+                p.cells_to_explorers,
+                p.explorer_requests
+            )?;
+        }
+        writeln!(f, "survival rate: {:.2}", self.survival_rate())?;
+        writeln!(f, "rockets built: {}", self.rockets_built())?;
+        write!(f, "cells spent on explorers: {}", self.cells_to_explorers())
+    }
+}
+
+struct SimPlanet {
+    harness: Option<PlanetHarness>,
+    explorer: Option<ExplorerHandle>,
+    has_rocket: bool,
+    report: PlanetReport,
+}
+
+/// Run the simulation, returning an error if the strategy is unknown or a planet stops answering
+pub fn simulate(config: &GalaxyConfig) -> Result<GalaxyReport, HarnessError> {
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let mut planets = Vec::new();
+
+    for planet_id in 1..=config.planets {
+        let Some(strategy) = strategy::by_name(&config.strategy) else {
+            return Err(HarnessError::Creation(PlanetCreationError::Other(format!(
+                "unknown strategy {}",
+                config.strategy
+            ))));
+        };
+        let harness =
+            PlanetHarness::spawn_with(planet_id, PlanetBuilder::new(planet_id).strategy(strategy))?;
+        let explorer = harness.attach_explorer(1000 + planet_id)?;
+        planets.push(SimPlanet {
+            harness: Some(harness),
+            explorer: Some(explorer),
+            has_rocket: false,
+            report: PlanetReport {
+                planet_id,
+                ..PlanetReport::default()
+            },
+        });
+    }
+
+    for tick in 0..config.ticks {
+        for planet in planets.iter_mut() {
+            // draw even for destroyed planets, so the sequence doesn't depend on their outcome
+            let sunray = rng.random_bool(config.sunray_probability);
+            let asteroid = rng.random_bool(config.asteroid_probability);
+            let request = rng.random_bool(config.explorer_probability);
+
+            let Some(harness) = planet.harness.as_ref() else {
+                continue;
+            };
+
+            if sunray {
+                harness.send_sunray()?;
+                planet.report.sunrays += 1;
+                // count the rockets built while handling the sunray
+                let has_rocket = harness.request_state()?.has_rocket;
+                if has_rocket && !planet.has_rocket {
+                    planet.report.rockets_built += 1;
+                }
+                planet.has_rocket = has_rocket;
+            }
+
+            if asteroid {
+                planet.report.asteroids += 1;
+                match harness.send_asteroid()? {
+                    Some(_rocket) => {
+                        // a rocket built on demand never showed up in the state
+                        if !planet.has_rocket {
+                            planet.report.rockets_built += 1;
+                        }
+                        planet.has_rocket = false;
+                    }
+                    None => {
+                        planet.report.destroyed_at = Some(tick);
+                        planet.explorer = None;
+                        planet.harness = None;
+                        continue;
+                    }
+                }
+            }
+
+            if request && let Some(explorer) = planet.explorer.as_ref() {
+                planet.report.explorer_requests += 1;
+                match explorer.request(ExplorerToPlanet::GenerateResourceRequest {
+                    explorer_id: explorer.explorer_id(),
+                    resource: BasicResourceType::Silicon,
+                })? {
+                    PlanetToExplorer::GenerateResourceResponse { resource: Some(_) } => {
+                        planet.report.cells_to_explorers += 1;
+                    }
+                    PlanetToExplorer::GenerateResourceResponse { resource: None } => {}
+                    other => return Err(HarnessError::UnexpectedExplorerResponse(other)),
+                }
+            }
+        }
+    }
+
+    Ok(GalaxyReport {
+        planets: planets.into_iter().map(|p| p.report).collect(),
+    })
+}
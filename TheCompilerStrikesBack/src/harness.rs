@@ -0,0 +1,348 @@
+use crate::PlanetCreationError;
+use crate::planet::{PlanetBuilder, try_create_planet_of_type};
+use crate::strategy::PlanetStrategy;
+use common_game::components::asteroid::Asteroid;
+use common_game::components::planet::{DummyPlanetState, Planet, PlanetType};
+use common_game::components::resource::{
+    BasicResource, BasicResourceType, ComplexResource, ComplexResourceRequest, ComplexResourceType,
+    GenericResource,
+};
+use common_game::components::rocket::Rocket;
+use common_game::components::sunray::Sunray;
+use common_game::protocols::orchestrator_planet::{OrchestratorToPlanet, PlanetToOrchestrator};
+use common_game::protocols::planet_explorer::{ExplorerToPlanet, PlanetToExplorer};
+use crossbeam_channel::{Receiver, RecvTimeoutError, Sender, bounded, unbounded};
+use std::fmt;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/*
+   Orchestrator stand-in used by tests and demos:
+   it owns the channels of a planet running in its own thread,
+   performs the StartPlanetAI handshake and kills the planet when dropped.
+
+   Every helper waits for the planet response at most `timeout`.
+*/
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug)]
+pub enum HarnessError {
+    /// The planet didn't answer in time
+    Timeout,
+    /// The planet thread is gone
+    Disconnected,
+    /// The planet answered with an unexpected message
+    UnexpectedResponse(PlanetToOrchestrator),
+    /// The planet answered an explorer with an unexpected message
+    UnexpectedExplorerResponse(PlanetToExplorer),
+    /// The planet configuration was rejected
+    Creation(PlanetCreationError),
+}
+
+impl fmt::Display for HarnessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HarnessError::Timeout => write!(f, "the planet didn't answer in time"),
+            HarnessError::Disconnected => write!(f, "the planet is disconnected"),
+            HarnessError::UnexpectedResponse(msg) => write!(f, "unexpected response: {msg:?}"),
+            HarnessError::UnexpectedExplorerResponse(msg) => {
+                write!(f, "unexpected explorer response: {msg:?}")
+            }
+            HarnessError::Creation(err) => write!(f, "the planet can't be created: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for HarnessError {}
+
+impl From<PlanetCreationError> for HarnessError {
+    fn from(err: PlanetCreationError) -> Self {
+        HarnessError::Creation(err)
+    }
+}
+
+impl From<RecvTimeoutError> for HarnessError {
+    fn from(err: RecvTimeoutError) -> Self {
+        match err {
+            RecvTimeoutError::Timeout => HarnessError::Timeout,
+            RecvTimeoutError::Disconnected => HarnessError::Disconnected,
+        }
+    }
+}
+
+/// A started planet driven from the calling thread
+pub struct PlanetHarness {
+    planet_id: u32,
+    tx_orch: Sender<OrchestratorToPlanet>,
+    rx_orch: Receiver<PlanetToOrchestrator>,
+    tx_explorer: Sender<ExplorerToPlanet>,
+    handle: Option<JoinHandle<Result<(), String>>>,
+    timeout: Duration,
+}
+
+impl PlanetHarness {
+    /// Spawn the TheCompilerStrikesBack planet with a strategy and start its AI
+    pub fn spawn(planet_id: u32, strategy: Box<dyn PlanetStrategy>) -> Result<Self, HarnessError> {
+        Self::spawn_with(planet_id, PlanetBuilder::new(planet_id).strategy(strategy))
+    }
+
+    /// Spawn a planet configured through a builder and start its AI
+    pub fn spawn_with(planet_id: u32, builder: PlanetBuilder) -> Result<Self, HarnessError> {
+        Self::try_spawn(planet_id, |rx_orch, tx_orch, rx_explorer| {
+            Ok(builder.build(rx_orch, tx_orch, rx_explorer)?)
+        })
+    }
+
+    /// Spawn a planet of any type with the strategy chosen by `try_create_planet_of_type`
+    /// and start its AI
+    pub fn spawn_of_type(
+        planet_id: u32,
+        planet_type: PlanetType,
+        gen_rules: Vec<BasicResourceType>,
+        comb_rules: Vec<ComplexResourceType>,
+    ) -> Result<Self, HarnessError> {
+        Self::try_spawn(planet_id, |rx_orch, tx_orch, rx_explorer| {
+            Ok(try_create_planet_of_type(
+                rx_orch,
+                tx_orch,
+                rx_explorer,
+                planet_id,
+                planet_type,
+                gen_rules,
+                comb_rules,
+            )?)
+        })
+    }
+
+    /// Spawn the planet returned by `create`, wired to the harness channels, and start its AI
+    pub fn spawn_custom(
+        planet_id: u32,
+        create: impl FnOnce(
+            Receiver<OrchestratorToPlanet>,
+            Sender<PlanetToOrchestrator>,
+            Receiver<ExplorerToPlanet>,
+        ) -> Planet,
+    ) -> Result<Self, HarnessError> {
+        Self::try_spawn(planet_id, |rx_orch, tx_orch, rx_explorer| {
+            Ok(create(rx_orch, tx_orch, rx_explorer))
+        })
+    }
+
+    fn try_spawn(
+        planet_id: u32,
+        create: impl FnOnce(
+            Receiver<OrchestratorToPlanet>,
+            Sender<PlanetToOrchestrator>,
+            Receiver<ExplorerToPlanet>,
+        ) -> Result<Planet, HarnessError>,
+    ) -> Result<Self, HarnessError> {
+        let (tx_orch, rx_planet) = unbounded();
+        let (tx_planet, rx_orch) = unbounded();
+        let (tx_explorer, rx_explorer) = unbounded();
+
+        let mut planet = create(rx_planet, tx_planet, rx_explorer)?;
+        let handle = thread::spawn(move || planet.run());
+
+        let harness = Self {
+            planet_id,
+            tx_orch,
+            rx_orch,
+            tx_explorer,
+            handle: Some(handle),
+            timeout: DEFAULT_TIMEOUT,
+        };
+        harness.start()?;
+        Ok(harness)
+    }
+
+    /// Change how long the helpers wait for a response
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn planet_id(&self) -> u32 {
+        self.planet_id
+    }
+
+    /// Send a message to the planet and wait for the response
+    pub fn request(&self, msg: OrchestratorToPlanet) -> Result<PlanetToOrchestrator, HarnessError> {
+        self.tx_orch
+            .send(msg)
+            .map_err(|_| HarnessError::Disconnected)?;
+        Ok(self.rx_orch.recv_timeout(self.timeout)?)
+    }
+
+    /// Message sent by the planet without being asked, if any
+    pub fn try_recv(&self) -> Option<PlanetToOrchestrator> {
+        self.rx_orch.try_recv().ok()
+    }
+
+    pub fn start(&self) -> Result<(), HarnessError> {
+        match self.request(OrchestratorToPlanet::StartPlanetAI)? {
+            PlanetToOrchestrator::StartPlanetAIResult { .. } => Ok(()),
+            other => Err(HarnessError::UnexpectedResponse(other)),
+        }
+    }
+
+    pub fn stop(&self) -> Result<(), HarnessError> {
+        match self.request(OrchestratorToPlanet::StopPlanetAI)? {
+            PlanetToOrchestrator::StopPlanetAIResult { .. } => Ok(()),
+            other => Err(HarnessError::UnexpectedResponse(other)),
+        }
+    }
+
+    pub fn send_sunray(&self) -> Result<(), HarnessError> {
+        match self.request(OrchestratorToPlanet::Sunray(Sunray::default()))? {
+            PlanetToOrchestrator::SunrayAck { .. } => Ok(()),
+            other => Err(HarnessError::UnexpectedResponse(other)),
+        }
+    }
+
+    /// Send an asteroid, returning the rocket used by the planet to defend itself
+    pub fn send_asteroid(&self) -> Result<Option<Rocket>, HarnessError> {
+        match self.request(OrchestratorToPlanet::Asteroid(Asteroid::default()))? {
+            PlanetToOrchestrator::AsteroidAck { rocket, .. } => Ok(rocket),
+            other => Err(HarnessError::UnexpectedResponse(other)),
+        }
+    }
+
+    pub fn request_state(&self) -> Result<DummyPlanetState, HarnessError> {
+        match self.request(OrchestratorToPlanet::InternalStateRequest)? {
+            PlanetToOrchestrator::InternalStateResponse { planet_state, .. } => Ok(planet_state),
+            other => Err(HarnessError::UnexpectedResponse(other)),
+        }
+    }
+
+    /// Move an explorer onto the planet, returning its endpoint
+    pub fn attach_explorer(&self, explorer_id: u32) -> Result<ExplorerHandle, HarnessError> {
+        let (tx, rx) = bounded(16);
+        match self.request(OrchestratorToPlanet::IncomingExplorerRequest {
+            explorer_id,
+            new_sender: tx,
+        })? {
+            PlanetToOrchestrator::IncomingExplorerResponse { res: Ok(()), .. } => {
+                Ok(ExplorerHandle {
+                    explorer_id,
+                    tx: self.tx_explorer.clone(),
+                    rx,
+                    timeout: self.timeout,
+                })
+            }
+            other => Err(HarnessError::UnexpectedResponse(other)),
+        }
+    }
+
+    pub fn detach_explorer(&self, explorer_id: u32) -> Result<(), HarnessError> {
+        match self.request(OrchestratorToPlanet::OutgoingExplorerRequest { explorer_id })? {
+            PlanetToOrchestrator::OutgoingExplorerResponse { res: Ok(()), .. } => Ok(()),
+            other => Err(HarnessError::UnexpectedResponse(other)),
+        }
+    }
+
+    /// Kill the planet and wait for its thread, returning the result of `Planet::run`
+    pub fn kill(mut self) -> Result<Result<(), String>, HarnessError> {
+        self.shutdown()
+    }
+
+    fn shutdown(&mut self) -> Result<Result<(), String>, HarnessError> {
+        let Some(handle) = self.handle.take() else {
+            return Err(HarnessError::Disconnected);
+        };
+        let response = self.request(OrchestratorToPlanet::KillPlanet);
+        let killed = match response {
+            Ok(PlanetToOrchestrator::KillPlanetResult { .. }) => Ok(()),
+            Ok(other) => Err(HarnessError::UnexpectedResponse(other)),
+            Err(err) => Err(err),
+        };
+        // a planet that didn't acknowledge the kill may still be running: don't block on it
+        if killed.is_err() && !handle.is_finished() {
+            return killed.map(|_| Ok(()));
+        }
+        let result = handle.join().map_err(|_| HarnessError::Disconnected)?;
+        killed.map(|_| result)
+    }
+}
+
+impl Drop for PlanetHarness {
+    fn drop(&mut self) {
+        let _ = self.shutdown();
+    }
+}
+
+/// Endpoint of an explorer attached to a harness planet
+pub struct ExplorerHandle {
+    explorer_id: u32,
+    tx: Sender<ExplorerToPlanet>,
+    rx: Receiver<PlanetToExplorer>,
+    timeout: Duration,
+}
+
+impl ExplorerHandle {
+    pub fn explorer_id(&self) -> u32 {
+        self.explorer_id
+    }
+
+    /// Send a message to the planet without waiting for a response
+    pub fn send(&self, msg: ExplorerToPlanet) -> Result<(), HarnessError> {
+        self.tx.send(msg).map_err(|_| HarnessError::Disconnected)
+    }
+
+    /// Wait for the next message from the planet
+    pub fn recv(&self) -> Result<PlanetToExplorer, HarnessError> {
+        Ok(self.rx.recv_timeout(self.timeout)?)
+    }
+
+    /// Message already sent by the planet, if any
+    pub fn try_recv(&self) -> Option<PlanetToExplorer> {
+        self.rx.try_recv().ok()
+    }
+
+    /// Send a message to the planet and wait for the response
+    pub fn request(&self, msg: ExplorerToPlanet) -> Result<PlanetToExplorer, HarnessError> {
+        self.send(msg)?;
+        self.recv()
+    }
+
+    /// Ask for a basic resource
+    pub fn generate(
+        &self,
+        resource: BasicResourceType,
+    ) -> Result<Option<BasicResource>, HarnessError> {
+        match self.request(ExplorerToPlanet::GenerateResourceRequest {
+            explorer_id: self.explorer_id,
+            resource,
+        })? {
+            PlanetToExplorer::GenerateResourceResponse { resource } => Ok(resource),
+            other => Err(HarnessError::UnexpectedExplorerResponse(other)),
+        }
+    }
+
+    /// Ask for a complex resource, getting the inputs back if the planet refuses
+    pub fn combine(
+        &self,
+        msg: ComplexResourceRequest,
+    ) -> Result<Result<ComplexResource, (String, GenericResource, GenericResource)>, HarnessError>
+    {
+        match self.request(ExplorerToPlanet::CombineResourceRequest {
+            explorer_id: self.explorer_id,
+            msg,
+        })? {
+            PlanetToExplorer::CombineResourceResponse { complex_response } => Ok(complex_response),
+            other => Err(HarnessError::UnexpectedExplorerResponse(other)),
+        }
+    }
+
+    /// Ask how many charged cells the planet offers
+    pub fn available_cells(&self) -> Result<u32, HarnessError> {
+        match self.request(ExplorerToPlanet::AvailableEnergyCellRequest {
+            explorer_id: self.explorer_id,
+        })? {
+            PlanetToExplorer::AvailableEnergyCellResponse { available_cells } => {
+                Ok(available_cells)
+            }
+            other => Err(HarnessError::UnexpectedExplorerResponse(other)),
+        }
+    }
+}
@@ -1,3 +1,29 @@
+pub mod allocation;
+pub mod conservation;
+pub mod economy;
+pub mod error;
+pub mod events;
+pub mod fairness;
+#[cfg(feature = "testing")]
+pub mod galaxy;
+#[cfg(feature = "testing")]
+pub mod harness;
+mod logger;
+pub mod metrics;
+mod names;
 pub mod planet;
 mod planet_ai;
-mod logger;
+pub mod planner;
+pub mod queue;
+mod relay;
+mod reservation;
+pub mod risk;
+pub mod sessions;
+#[cfg(feature = "testing")]
+pub mod sim_explorer;
+pub mod snapshot;
+pub mod strategy;
+pub mod trace;
+
+pub use error::PlanetCreationError;
+pub use planet_ai::{DefenseReserve, EnergyBudget};
@@ -1,3 +1,4 @@
+use crate::events::PlanetEvent;
 use crate::planet_ai::AI;
 use common_game::logging::ActorType::*;
 use common_game::logging::Channel::*;
@@ -14,35 +15,118 @@ use common_game::logging::{EventType, LogEvent, Participant, Payload};
    - KillPlanetResult
    - OutgoingExplorerResponse
    - IncomingExplorerResponse
+
+   Explorer requests are logged as well, since the explorers only see the outcome
+   and not the reason behind it.
+   Every event is also captured by the event log given to the planet builder, if any,
+   and counted by its metrics registry.
 */
 
 impl AI {
-    pub fn log_charge_cell(&self, detail: String) {
+    pub fn log(&self, event: PlanetEvent) {
         let mut payload = Payload::new();
-        payload.insert("Energy cell".to_string(), detail);
+        payload.insert("event".to_string(), event.name().to_string());
+        payload.insert(
+            "asteroid risk".to_string(),
+            format!("{:.3}", self.risk.estimate()),
+        );
 
-        LogEvent::new(
-            Some(Participant::new(Orchestrator, 0_u32)),
-            Some(self.log_part.clone()),
-            EventType::InternalPlanetAction,
-            Debug,
-            payload,
-        )
-        .emit();
-    }
+        let channel = match &event {
+            PlanetEvent::RequestDenied {
+                request, reason, ..
+            } => {
+                payload.insert("request".to_string(), format!("{request:?}"));
+                payload.insert("reason".to_string(), reason.clone());
+                Info
+            }
+            PlanetEvent::RequestServed { request, .. }
+            | PlanetEvent::RequestQueued { request, .. } => {
+                payload.insert("request".to_string(), format!("{request:?}"));
+                Debug
+            }
+            PlanetEvent::RequestRejected { request, .. } => {
+                payload.insert("request".to_string(), format!("{request:?}"));
+                Warning
+            }
+            PlanetEvent::ResponseUndeliverable { .. } => Warning,
+            PlanetEvent::ExplorerLeft {
+                requests,
+                resources_handed_out,
+                ..
+            } => {
+                payload.insert("requests".to_string(), requests.to_string());
+                payload.insert(
+                    "resources handed out".to_string(),
+                    resources_handed_out.to_string(),
+                );
+                Debug
+            }
+            PlanetEvent::ResourcesNotConserved {
+                requested, sent, ..
+            } => {
+                payload.insert("requested".to_string(), format!("{requested:?}"));
+                payload.insert("sent".to_string(), format!("{sent:?}"));
+                Warning
+            }
+            PlanetEvent::CellWasted { total_wasted } => {
+                payload.insert("total wasted".to_string(), total_wasted.to_string());
+                Debug
+            }
+            PlanetEvent::RocketBuildFailed {
+                reason,
+                total_failed,
+            } => {
+                payload.insert("reason".to_string(), reason.clone());
+                payload.insert("total failed".to_string(), total_failed.to_string());
+                Warning
+            }
+            PlanetEvent::LedgerReported { accounts } => {
+                for (explorer_id, account) in accounts {
+                    payload.insert(
+                        format!("explorer {explorer_id} credit"),
+                        format!(
+                            "balance {} earned {} spent {}",
+                            account.balance, account.earned, account.spent
+                        ),
+                    );
+                }
+                Debug
+            }
+            PlanetEvent::SnapshotFailed { reason } => {
+                payload.insert("reason".to_string(), reason.clone());
+                Warning
+            }
+            PlanetEvent::CellCharged
+            | PlanetEvent::ExplorerArrived { .. }
+            | PlanetEvent::CellReserved { .. }
+            | PlanetEvent::ReservationExpired { .. }
+            | PlanetEvent::ReservationDropped { .. }
+            | PlanetEvent::RocketBuilt
+            | PlanetEvent::RocketLaunched
+            | PlanetEvent::SnapshotSaved
+            | PlanetEvent::SnapshotRestored => Debug,
+        };
 
-    pub fn log_build_rocket(&self) {
-        let mut payload = Payload::new();
-        payload.insert("Rocket".to_string(), "Built".to_string());
+        // internal actions are addressed to the planet itself
+        let receiver = match event.explorer_id() {
+            Some(explorer_id) => Participant::new(Explorer, explorer_id),
+            None => self.log_part.clone(),
+        };
 
         LogEvent::new(
-            Some(Participant::new(Orchestrator, 0_u32)),
             Some(self.log_part.clone()),
+            Some(receiver),
             EventType::InternalPlanetAction,
-            Debug,
+            channel,
             payload,
         )
         .emit();
-    }
 
+        if let Some(metrics) = &self.metrics {
+            metrics.record_event(self.log_part.id, &event);
+        }
+        if let Some(event_log) = &self.event_log {
+            event_log.record(self.log_part.id, event);
+        }
+    }
 }
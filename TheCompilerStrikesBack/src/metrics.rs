@@ -0,0 +1,187 @@
+use crate::events::PlanetEvent;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/*
+   Operational counters of one or more planets.
+
+   The AI updates the counters on every callback, most of them from the events it logs,
+   and the registry renders them in the Prometheus text format: every sample is labelled
+   with the planet id, explorer requests also with the request variant and its outcome.
+*/
+
+/// Name, help text and value of every counter sample labelled only with the planet
+type Counter = (&'static str, &'static str, fn(&PlanetCounters) -> u64);
+
+const COUNTERS: [Counter; 7] = [
+    ("planet_sunrays_received_total", "Sunrays received", |c| {
+        c.sunrays_received
+    }),
+    ("planet_cells_charged_total", "Energy cells charged", |c| {
+        c.cells_charged
+    }),
+    (
+        "planet_sunrays_wasted_total",
+        "Sunrays discarded because every cell was charged",
+        |c| c.sunrays_wasted,
+    ),
+    ("planet_rockets_built_total", "Rockets built", |c| {
+        c.rockets_built
+    }),
+    (
+        "planet_rocket_build_failures_total",
+        "Rocket builds that returned an error",
+        |c| c.rocket_build_failures,
+    ),
+    (
+        "planet_asteroids_survived_total",
+        "Asteroids stopped by a rocket",
+        |c| c.asteroids_survived,
+    ),
+    (
+        "planet_asteroids_failed_total",
+        "Asteroids that found no rocket",
+        |c| c.asteroids_failed,
+    ),
+];
+
+/// Counters of a single planet
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PlanetCounters {
+    pub sunrays_received: u64,
+    pub cells_charged: u64,
+    pub sunrays_wasted: u64,
+    pub rockets_built: u64,
+    pub rocket_build_failures: u64,
+    pub asteroids_survived: u64,
+    pub asteroids_failed: u64,
+    /// Explorer requests by (request variant, outcome)
+    pub explorer_requests: BTreeMap<(String, &'static str), u64>,
+}
+
+impl PlanetCounters {
+    /// Explorer requests of a variant with a given outcome ("served", "denied", "rejected" or "queued")
+    pub fn explorer_requests(&self, request: &str, outcome: &str) -> u64 {
+        self.explorer_requests
+            .iter()
+            .filter(|((r, o), _)| r == request && *o == outcome)
+            .map(|(_, count)| count)
+            .sum()
+    }
+}
+
+/// Counters of one or more planets, keyed by planet id and rendered in the Prometheus text format.
+#[derive(Debug, Clone, Default)]
+pub struct PlanetMetrics {
+    planets: Arc<Mutex<BTreeMap<u32, PlanetCounters>>>,
+}
+
+impl PlanetMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn update(&self, planet_id: u32, update: impl FnOnce(&mut PlanetCounters)) {
+        let mut planets = self.planets.lock().unwrap();
+        update(planets.entry(planet_id).or_default());
+    }
+
+    pub fn record_sunray(&self, planet_id: u32) {
+        self.update(planet_id, |c| c.sunrays_received += 1);
+    }
+
+    pub fn record_asteroid(&self, planet_id: u32, survived: bool) {
+        self.update(planet_id, |c| {
+            if survived {
+                c.asteroids_survived += 1;
+            } else {
+                c.asteroids_failed += 1;
+            }
+        });
+    }
+
+    /// Update the counters tracked through the events logged by the AI
+    pub fn record_event(&self, planet_id: u32, event: &PlanetEvent) {
+        self.update(planet_id, |c| match event {
+            PlanetEvent::CellCharged => c.cells_charged += 1,
+            PlanetEvent::CellWasted { .. } => c.sunrays_wasted += 1,
+            PlanetEvent::RocketBuilt => c.rockets_built += 1,
+            PlanetEvent::RocketBuildFailed { .. } => c.rocket_build_failures += 1,
+            PlanetEvent::RequestServed { request, .. } => {
+                *c.explorer_requests
+                    .entry((format!("{request:?}"), "served"))
+                    .or_default() += 1
+            }
+            PlanetEvent::RequestDenied { request, .. } => {
+                *c.explorer_requests
+                    .entry((format!("{request:?}"), "denied"))
+                    .or_default() += 1
+            }
+            PlanetEvent::RequestRejected { request, .. } => {
+                *c.explorer_requests
+                    .entry((format!("{request:?}"), "rejected"))
+                    .or_default() += 1
+            }
+            PlanetEvent::RequestQueued { request, .. } => {
+                *c.explorer_requests
+                    .entry((format!("{request:?}"), "queued"))
+                    .or_default() += 1
+            }
+            PlanetEvent::RocketLaunched
+            | PlanetEvent::CellReserved { .. }
+            | PlanetEvent::ReservationExpired { .. }
+            | PlanetEvent::ReservationDropped { .. }
+            | PlanetEvent::ResponseUndeliverable { .. }
+            | PlanetEvent::ExplorerArrived { .. }
+            | PlanetEvent::ExplorerLeft { .. }
+            | PlanetEvent::ResourcesNotConserved { .. }
+            | PlanetEvent::LedgerReported { .. }
+            | PlanetEvent::SnapshotSaved
+            | PlanetEvent::SnapshotRestored
+            | PlanetEvent::SnapshotFailed { .. } => {}
+        });
+    }
+
+    /// Counters of a planet, if it recorded anything
+    pub fn counters(&self, planet_id: u32) -> Option<PlanetCounters> {
+        self.planets.lock().unwrap().get(&planet_id).cloned()
+    }
+
+    /// Snapshot of every counter in the Prometheus text format
+    pub fn render(&self) -> String {
+        let planets = self.planets.lock().unwrap();
+        let mut out = String::new();
+
+        for (name, help, value) in COUNTERS {
+            let _ = writeln!(out, "# HELP {name} {help}");
+            let _ = writeln!(out, "# TYPE {name} counter");
+            for (planet_id, c) in planets.iter() {
+                let _ = writeln!(out, "{name}{{planet=\"{planet_id}\"}} {}", value(c));
+            }
+        }
+
+        let name = "planet_explorer_requests_total";
+        let _ = writeln!(
+            out,
+            "# HELP {name} Explorer requests by variant and outcome"
+        );
+        let _ = writeln!(out, "# TYPE {name} counter");
+        for (planet_id, c) in planets.iter() {
+            for ((request, outcome), count) in &c.explorer_requests {
+                let _ = writeln!(
+                    out,
+                    "{name}{{planet=\"{planet_id}\",request=\"{request}\",outcome=\"{outcome}\"}} {count}"
+                );
+            }
+        }
+        out
+    }
+
+    /// Write the snapshot returned by [`PlanetMetrics::render`] to a file
+    pub fn write_to(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        std::fs::write(path, self.render())
+    }
+}
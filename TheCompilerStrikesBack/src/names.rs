@@ -0,0 +1,74 @@
+use common_game::components::resource::{BasicResourceType, ComplexResourceType, ResourceType};
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serializer};
+use std::fmt::Debug;
+
+/*
+   Names of the resource types, which common-game doesn't make serializable.
+   Snapshots and traces write them with their Debug representation, "Oxygen" or "Water".
+*/
+
+pub(crate) trait Named: Debug + Copy + Sized + 'static {
+    const ALL: &'static [Self];
+
+    fn name(&self) -> String {
+        format!("{self:?}")
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().find(|item| item.name() == name).copied()
+    }
+}
+
+impl Named for BasicResourceType {
+    const ALL: &'static [Self] = &[
+        BasicResourceType::Oxygen,
+        BasicResourceType::Hydrogen,
+        BasicResourceType::Carbon,
+        BasicResourceType::Silicon,
+    ];
+}
+
+impl Named for ComplexResourceType {
+    const ALL: &'static [Self] = &[
+        ComplexResourceType::Water,
+        ComplexResourceType::Diamond,
+        ComplexResourceType::Life,
+        ComplexResourceType::Robot,
+        ComplexResourceType::Dolphin,
+        ComplexResourceType::AIPartner,
+    ];
+}
+
+pub(crate) fn resource_name(resource: ResourceType) -> String {
+    match resource {
+        ResourceType::Basic(basic) => basic.name(),
+        ResourceType::Complex(complex) => complex.name(),
+    }
+}
+
+pub(crate) fn resource_from_name(name: &str) -> Option<ResourceType> {
+    BasicResourceType::from_name(name)
+        .map(ResourceType::Basic)
+        .or_else(|| ComplexResourceType::from_name(name).map(ResourceType::Complex))
+}
+
+/// `serialize_with` for a list of resource types
+pub(crate) fn to_names<S: Serializer, T: Named>(
+    items: &[T],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(items.iter().map(Named::name))
+}
+
+/// `deserialize_with` for a list of resource types
+pub(crate) fn from_names<'de, D: Deserializer<'de>, T: Named>(
+    deserializer: D,
+) -> Result<Vec<T>, D::Error> {
+    Vec::<String>::deserialize(deserializer)?
+        .into_iter()
+        .map(|name| {
+            T::from_name(&name).ok_or_else(|| D::Error::custom(format!("unknown resource {name}")))
+        })
+        .collect()
+}
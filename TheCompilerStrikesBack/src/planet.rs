@@ -3,40 +3,388 @@ PLANET CONFIGURATION:
     - TheCompilerStrikesBack type: C
     - base resource: Silicon
     - complex resource: Robot, Diamond, AI partner
+
+The AI serves whatever the generation and combination rules allow,
+so planets of the other types can be created with `try_create_planet_of_type`.
  */
 
-use crate::planet_ai::AI;
+use crate::allocation::{CellPolicy, CellStatistics};
+use crate::conservation::ConservationAudit;
+use crate::economy::EconomyConfig;
+use crate::error::PlanetCreationError;
+use crate::events::EventLog;
+use crate::fairness::FairnessPolicy;
+use crate::metrics::PlanetMetrics;
+use crate::planet_ai::{AI, DefenseReserve};
+use crate::queue::{ExplorerDirectory, QueueConfig};
+use crate::relay::Relays;
+use crate::risk::AsteroidRisk;
+use crate::sessions::SessionRegistry;
+use crate::snapshot::PlanetSnapshot;
+use crate::strategy;
+use crate::strategy::PlanetStrategy;
+use crate::trace::TraceRecorder;
 use common_game::components::planet::{Planet, PlanetType};
 use common_game::components::resource::{BasicResourceType, ComplexResourceType};
+use common_game::logging::ActorType;
+use common_game::logging::Participant;
 use common_game::protocols::orchestrator_planet::{OrchestratorToPlanet, PlanetToOrchestrator};
 use common_game::protocols::planet_explorer::ExplorerToPlanet;
 use crossbeam_channel::{Receiver, Sender};
+use std::path::PathBuf;
+
+/// Builder for a planet driven by our AI.
+///
+/// Every option defaults to the TheCompilerStrikesBack configuration
+/// (type C, Silicon generation, Robot/AIPartner/Diamond combination).
+/// Without a strategy the planet uses the default one of its type, see [`strategy::default_for`].
+///
+/// The observation handles it takes, such as [`EventLog`], are shared: give a clone to the
+/// builder and keep the original to read what the planet records.
+pub struct PlanetBuilder {
+    id: u32,
+    planet_type: PlanetType,
+    gen_rules: Vec<BasicResourceType>,
+    comb_rules: Vec<ComplexResourceType>,
+    strategy: Option<Box<dyn PlanetStrategy>>,
+    log_part: Participant,
+    queue: Option<QueueConfig>,
+    fairness: FairnessPolicy,
+    defense_reserve: DefenseReserve,
+    risk: AsteroidRisk,
+    sessions: Option<SessionRegistry>,
+    economy: Option<EconomyConfig>,
+    reservation_events: Option<u32>,
+    cell_policy: CellPolicy,
+    cell_statistics: Option<CellStatistics>,
+    event_log: Option<EventLog>,
+    metrics: Option<PlanetMetrics>,
+    audit: Option<ConservationAudit>,
+    snapshot_path: Option<PathBuf>,
+    restore: Option<PlanetSnapshot>,
+    trace: Option<TraceRecorder>,
+}
+
+impl PlanetBuilder {
+    pub fn new(planet_id: u32) -> Self {
+        Self {
+            id: planet_id,
+            planet_type: PlanetType::C,
+            gen_rules: vec![BasicResourceType::Silicon],
+            comb_rules: vec![
+                ComplexResourceType::Robot,
+                ComplexResourceType::AIPartner,
+                ComplexResourceType::Diamond,
+            ],
+            strategy: None,
+            log_part: Participant::new(ActorType::Planet, planet_id),
+            queue: None,
+            fairness: FairnessPolicy::Unlimited,
+            defense_reserve: DefenseReserve::default(),
+            risk: AsteroidRisk::default(),
+            sessions: None,
+            economy: None,
+            reservation_events: None,
+            cell_policy: CellPolicy::default(),
+            cell_statistics: None,
+            event_log: None,
+            metrics: None,
+            audit: None,
+            snapshot_path: None,
+            restore: None,
+            trace: None,
+        }
+    }
+
+    /// Builder for a planet of any type, with its own generation and combination rules
+    pub fn of_type(
+        planet_id: u32,
+        planet_type: PlanetType,
+        gen_rules: Vec<BasicResourceType>,
+        comb_rules: Vec<ComplexResourceType>,
+    ) -> Self {
+        Self::new(planet_id)
+            .planet_type(planet_type)
+            .gen_rules(gen_rules)
+            .comb_rules(comb_rules)
+    }
+
+    pub fn planet_type(mut self, planet_type: PlanetType) -> Self {
+        self.planet_type = planet_type;
+        self
+    }
+
+    pub fn gen_rules(mut self, gen_rules: Vec<BasicResourceType>) -> Self {
+        self.gen_rules = gen_rules;
+        self
+    }
+
+    pub fn comb_rules(mut self, comb_rules: Vec<ComplexResourceType>) -> Self {
+        self.comb_rules = comb_rules;
+        self
+    }
+
+    /// Strategy deciding whether the charged cells become rockets or stay for explorers.
+    ///
+    /// This is the supported way to choose the strategy: [`create_planet`] keeps the signature
+    /// the orchestrator calls, and uses the default strategy of the planet type like
+    /// [`try_create_planet_of_type`]
+    pub fn strategy(mut self, strategy: Box<dyn PlanetStrategy>) -> Self {
+        self.strategy = Some(strategy);
+        self
+    }
+
+    /// Participant used by the AI as the sender of its log events
+    pub fn log_participant(mut self, log_part: Participant) -> Self {
+        self.log_part = log_part;
+        self
+    }
+
+    /// Park the explorer requests that find no charged cell instead of refusing them.
+    ///
+    /// Deferred responses are sent through the explorer channels received with
+    /// `IncomingExplorerRequest`, so the orchestrator channel is relayed through a thread
+    /// that stops with the planet.
+    pub fn request_queue(mut self, config: QueueConfig) -> Self {
+        self.queue = Some(config);
+        self
+    }
+
+    /// How charged cells are shared between explorers
+    pub fn fairness(mut self, policy: FairnessPolicy) -> Self {
+        self.fairness = policy;
+        self
+    }
+
+    /// How many charged cells are kept for defense when explorers ask for the available ones
+    pub fn defense_reserve(mut self, policy: DefenseReserve) -> Self {
+        self.defense_reserve = policy;
+        self
+    }
+
+    /// Model used to estimate the asteroid risk
+    pub fn asteroid_risk(mut self, risk: AsteroidRisk) -> Self {
+        self.risk = risk;
+        self
+    }
+
+    /// Record the visits of the explorers in a session registry
+    pub fn sessions(mut self, sessions: SessionRegistry) -> Self {
+        self.sessions = Some(sessions);
+        self
+    }
+
+    /// Charge the explorers in credit for the resources they get
+    pub fn economy(mut self, config: EconomyConfig) -> Self {
+        self.economy = Some(config);
+        self
+    }
+
+    /// Hold a charged cell for every explorer offered one by `AvailableEnergyCellRequest`,
+    /// until it is served, leaves or `hold_events` sunrays and asteroids have passed since the offer.
+    ///
+    /// Other explorers and the rockets built on sunrays don't spend a held cell: with a
+    /// [`request_queue`](Self::request_queue), explorer requests wait for the hold to end.
+    /// An asteroid still turns a held cell into a rocket, dropping the hold
+    pub fn cell_reservations(mut self, hold_events: u32) -> Self {
+        self.reservation_events = Some(hold_events);
+        self
+    }
+
+    /// Which charged cell is spent first by explorer requests and rockets
+    pub fn cell_policy(mut self, policy: CellPolicy) -> Self {
+        self.cell_policy = policy;
+        self
+    }
+
+    /// Count how many times each cell fed a rocket or an explorer
+    pub fn cell_statistics(mut self, statistics: CellStatistics) -> Self {
+        self.cell_statistics = Some(statistics);
+        self
+    }
+
+    /// Capture the events logged by the AI in an event log
+    pub fn event_log(mut self, event_log: EventLog) -> Self {
+        self.event_log = Some(event_log);
+        self
+    }
+
+    /// Count the planet activity in a metrics registry
+    pub fn metrics(mut self, metrics: PlanetMetrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Check that combination responses hand back or consume every input (debug builds only)
+    pub fn conservation_audit(mut self, audit: ConservationAudit) -> Self {
+        self.audit = Some(audit);
+        self
+    }
+
+    /// Write a snapshot of the planet to `path` every time its AI is stopped
+    pub fn snapshot_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.snapshot_path = Some(path.into());
+        self
+    }
+
+    /// Resume the charged cells, the rocket and the counters of a snapshot
+    pub fn restore(mut self, snapshot: PlanetSnapshot) -> Self {
+        self.restore = Some(snapshot);
+        self
+    }
 
+    /// Write the orchestrator and explorer traffic of the planet to a trace file
+    pub fn trace(mut self, recorder: TraceRecorder) -> Self {
+        self.trace = Some(recorder);
+        self
+    }
+
+    /// Wire the planet to its channels and construct it
+    pub fn build(
+        self,
+        rx_orchestrator: Receiver<OrchestratorToPlanet>,
+        tx_orchestrator: Sender<PlanetToOrchestrator>,
+        rx_explorer: Receiver<ExplorerToPlanet>,
+    ) -> Result<Planet, PlanetCreationError> {
+        let strategy = self
+            .strategy
+            .unwrap_or_else(|| strategy::default_for(self.planet_type));
+        let mut ai = AI::new(self.log_part, strategy)
+            .with_planet_type(self.planet_type)
+            .with_fairness(self.fairness)
+            .with_defense_reserve(self.defense_reserve)
+            .with_risk(self.risk)
+            .with_cell_allocation(self.cell_policy, self.cell_statistics.unwrap_or_default());
+        let (mut rx_orchestrator, mut tx_orchestrator, mut rx_explorer) =
+            (rx_orchestrator, tx_orchestrator, rx_explorer);
+        let relays = Relays::default();
+        if let Some(recorder) = self.trace {
+            (rx_orchestrator, tx_orchestrator) =
+                recorder.wrap_orchestrator(rx_orchestrator, tx_orchestrator, &relays);
+            rx_explorer = recorder.wrap_explorer(rx_explorer, &relays);
+        }
+        if let Some(sessions) = self.sessions {
+            ai = ai.with_sessions(sessions);
+        }
+        if let Some(config) = self.economy {
+            ai = ai.with_economy(config);
+        }
+        if let Some(hold_events) = self.reservation_events {
+            ai = ai.with_cell_reservations(hold_events);
+        }
+        if let Some(event_log) = self.event_log {
+            ai = ai.with_event_log(event_log);
+        }
+        if let Some(metrics) = self.metrics {
+            ai = ai.with_metrics(metrics);
+        }
+        if let Some(audit) = self.audit {
+            ai = ai.with_conservation_audit(audit);
+        }
+        if let Some(path) = self.snapshot_path {
+            ai = ai.with_snapshot_path(path);
+        }
+        if let Some(snapshot) = self.restore {
+            ai = ai.with_restore(snapshot);
+        }
+        if let Some(config) = self.queue {
+            let directory = ExplorerDirectory::default();
+            rx_orchestrator = directory.relay(rx_orchestrator, &relays);
+            ai = ai.with_queue(config, directory);
+        }
+        ai = ai.with_relays(relays);
+
+        Planet::new(
+            self.id,
+            self.planet_type,
+            Box::new(ai),
+            self.gen_rules,
+            self.comb_rules,
+            (rx_orchestrator, tx_orchestrator),
+            rx_explorer,
+        )
+        .map_err(PlanetCreationError::from)
+    }
+}
+
+/// Create the TheCompilerStrikesBack planet, reporting an invalid configuration as an error.
+///
+/// The planet uses the defensive strategy, other strategies are set through
+/// [`PlanetBuilder::strategy`]
+pub fn try_create_planet(
+    rx_orchestrator: Receiver<OrchestratorToPlanet>,
+    tx_orchestrator: Sender<PlanetToOrchestrator>,
+    rx_explorer: Receiver<ExplorerToPlanet>,
+    planet_id: u32,
+) -> Result<Planet, PlanetCreationError> {
+    PlanetBuilder::new(planet_id).build(rx_orchestrator, tx_orchestrator, rx_explorer)
+}
+
+/// Create a planet of any type driven by our AI.
+///
+/// The planet uses the default strategy of its type, see [`strategy::default_for`]
+pub fn try_create_planet_of_type(
+    rx_orchestrator: Receiver<OrchestratorToPlanet>,
+    tx_orchestrator: Sender<PlanetToOrchestrator>,
+    rx_explorer: Receiver<ExplorerToPlanet>,
+    planet_id: u32,
+    planet_type: PlanetType,
+    gen_rules: Vec<BasicResourceType>,
+    comb_rules: Vec<ComplexResourceType>,
+) -> Result<Planet, PlanetCreationError> {
+    PlanetBuilder::of_type(planet_id, planet_type, gen_rules, comb_rules).build(
+        rx_orchestrator,
+        tx_orchestrator,
+        rx_explorer,
+    )
+}
+
+/// Create the TheCompilerStrikesBack planet, with the signature the orchestrator calls.
+///
+/// The planet uses the defensive strategy: to choose another one build the planet with
+/// [`PlanetBuilder::strategy`] instead.
+///
+/// Panics if the planet cannot be created, see [`try_create_planet`]
 pub fn create_planet(
     rx_orchestrator: Receiver<OrchestratorToPlanet>,
     tx_orchestrator: Sender<PlanetToOrchestrator>,
     rx_explorer: Receiver<ExplorerToPlanet>,
     planet_id: u32,
 ) -> Planet {
-    let id = planet_id;
-    let ai = AI::new(id);
-    let gen_rules = vec![BasicResourceType::Silicon];
-    let comb_rules = vec![
-        ComplexResourceType::Robot,
-        ComplexResourceType::AIPartner,
-        ComplexResourceType::Diamond,
-    ];
-
     // Construct the TheCompilerStrikesBack and return it
-    let planet_creation_result = Planet::new(
-        id,
-        PlanetType::C,
-        Box::new(ai),
-        gen_rules,
-        comb_rules,
-        (rx_orchestrator, tx_orchestrator),
-        rx_explorer,
-    );
+    let planet_creation_result =
+        try_create_planet(rx_orchestrator, tx_orchestrator, rx_explorer, planet_id);
 
     planet_creation_result.unwrap()
 }
+
+/// Recreate the planet saved in a snapshot,
+/// with the type, rules and strategy named in it
+pub fn create_planet_from_snapshot(
+    rx_orchestrator: Receiver<OrchestratorToPlanet>,
+    tx_orchestrator: Sender<PlanetToOrchestrator>,
+    rx_explorer: Receiver<ExplorerToPlanet>,
+    snapshot: PlanetSnapshot,
+) -> Result<Planet, PlanetCreationError> {
+    let Some(strategy) = strategy::by_name(&snapshot.strategy) else {
+        return Err(PlanetCreationError::Other(format!(
+            "unknown strategy {}",
+            snapshot.strategy
+        )));
+    };
+    let Some(planet_type) = snapshot.planet_type() else {
+        return Err(PlanetCreationError::Other(format!(
+            "unknown planet type {}",
+            snapshot.planet_type
+        )));
+    };
+    PlanetBuilder::of_type(
+        snapshot.planet_id,
+        planet_type,
+        snapshot.gen_rules.clone(),
+        snapshot.comb_rules.clone(),
+    )
+    .strategy(strategy)
+    .restore(snapshot)
+    .build(rx_orchestrator, tx_orchestrator, rx_explorer)
+}
@@ -1,78 +1,790 @@
-use common_game::components::planet::{DummyPlanetState, PlanetAI, PlanetState};
+use crate::allocation::{CellAllocator, CellPolicy, CellStatistics, CellUse};
+use crate::conservation::{AuditTicket, ConservationAudit, combination_types};
+use crate::economy::{Economy, EconomyConfig};
+use crate::events::{EventLog, PlanetEvent};
+use crate::fairness::{Fairness, FairnessPolicy};
+use crate::metrics::PlanetMetrics;
+use crate::queue::{Deferred, ExplorerDirectory, PendingQueue, QueueConfig};
+use crate::relay::Relays;
+use crate::reservation::Reservations;
+use crate::risk::AsteroidRisk;
+use crate::sessions::SessionRegistry;
+use crate::snapshot::PlanetSnapshot;
+use crate::strategy::PlanetStrategy;
+use common_game::components::planet::{DummyPlanetState, PlanetAI, PlanetState, PlanetType};
 use common_game::components::resource::{
-    BasicResource, BasicResourceType, Combinator, ComplexResource, ComplexResourceRequest,
-    Generator, GenericResource,
+    BasicResource, BasicResourceType, Combinator, ComplexResourceRequest, Generator,
+    GenericResource, ResourceType,
 };
 use common_game::components::rocket::Rocket;
 use common_game::components::sunray::Sunray;
-use common_game::logging::ActorType::Planet;
 use common_game::logging::Participant;
 use common_game::protocols::planet_explorer::PlanetToExplorer::{
     AvailableEnergyCellResponse, CombineResourceResponse, GenerateResourceResponse,
     SupportedCombinationResponse, SupportedResourceResponse,
 };
-use common_game::protocols::planet_explorer::{ExplorerToPlanet, PlanetToExplorer};
+use common_game::protocols::planet_explorer::{
+    ExplorerToPlanet, ExplorerToPlanetKind, PlanetToExplorer,
+};
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+/*
+   Energy accounting: the charged cells are split between the ones kept for defense
+   and the ones free for explorers, so that `AvailableEnergyCellRequest` doesn't offer
+   a cell the AI is about to turn into a rocket.
+   By default one cell is kept for the next rocket while the planet can have one and has none.
+   The split is only reported, generation and combination requests may still use every cell.
+*/
+
+/// How many charged cells are kept for defense
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DefenseReserve {
+    /// Every charged cell is free for explorers
+    Unreserved,
+    /// One cell is kept for the next rocket while the planet can have one and has none
+    #[default]
+    NextRocket,
+    /// A fixed number of cells is always kept
+    Cells(usize),
+    /// A share of the cells equal to the asteroid risk estimate is kept
+    Adaptive,
+}
+
+/// Charged cells split between defense and explorers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EnergyBudget {
+    pub charged: usize,
+    pub reserved: usize,
+}
+
+impl EnergyBudget {
+    pub fn new(
+        state: &DummyPlanetState,
+        can_have_rocket: bool,
+        policy: DefenseReserve,
+        risk: f64,
+    ) -> Self {
+        let wanted = match policy {
+            DefenseReserve::Unreserved => 0,
+            DefenseReserve::NextRocket => usize::from(can_have_rocket && !state.has_rocket),
+            DefenseReserve::Cells(cells) => cells,
+            DefenseReserve::Adaptive if can_have_rocket => {
+                (risk * state.energy_cells.len() as f64).round() as usize
+            }
+            DefenseReserve::Adaptive => 0,
+        };
+        Self {
+            charged: state.charged_cells_count,
+            reserved: wanted.min(state.charged_cells_count),
+        }
+    }
+
+    /// Charged cells free for explorers
+    pub fn available(&self) -> usize {
+        self.charged - self.reserved
+    }
+}
+
+const NO_CHARGED_CELL: &str = "There isn't any charged cell";
+const KEPT_FOR_ROCKET: &str = "The last charged cell is kept for the rocket";
+const QUOTA_EXCEEDED: &str = "The explorer exceeded its cell quota";
+const TIMED_OUT: &str = "The request timed out";
+const EXPLORER_LEFT: &str = "The explorer left the planet";
+const NOT_ON_PLANET: &str = "The explorer isn't on the planet";
+const NOT_ENOUGH_CREDIT: &str = "The explorer doesn't have enough credit";
+const CELLS_RESERVED: &str = "Every charged cell is reserved for other explorers";
+const OTHERS_FIRST: &str = "The charged cells are left to explorers that consumed fewer";
 
 pub struct AI {
     pub(crate) log_part: Participant,
+    pub(crate) strategy: Box<dyn PlanetStrategy>,
+    pub(crate) queue: Option<PendingQueue>,
+    pub(crate) fairness: Fairness,
+    pub(crate) sessions: SessionRegistry,
+    /// Prices of the served requests, everything is free without it
+    pub(crate) economy: Option<Economy>,
+    /// Cells held for the explorers that were offered one
+    pub(crate) reservations: Option<Reservations>,
+    /// Picks the charged cell spent by every request and rocket
+    pub(crate) cells: CellAllocator,
+    pub(crate) event_log: Option<EventLog>,
+    pub(crate) metrics: Option<PlanetMetrics>,
+    /// Checked against every combination response
+    pub(crate) audit: Option<ConservationAudit>,
+    pub(crate) defense_reserve: DefenseReserve,
+    pub(crate) risk: AsteroidRisk,
+    /// Sunrays discarded because every cell was already charged
+    pub(crate) wasted_sunrays: u32,
+    /// `build_rocket` calls that returned an error
+    pub(crate) failed_rocket_builds: u32,
+    /// Written in the snapshots, the planet state doesn't know it
+    pub(crate) planet_type: PlanetType,
+    /// Where the snapshot is written when the AI is stopped
+    pub(crate) snapshot_path: Option<PathBuf>,
+    /// Snapshot still to be replayed on the planet state
+    pub(crate) restore: Option<PlanetSnapshot>,
+    /// Threads relaying the planet channels, stopped when the AI is dropped with the planet
+    pub(crate) relays: Relays,
 }
 
 impl AI {
-    pub fn new(id: u32) -> Self {
+    pub fn new(log_part: Participant, strategy: Box<dyn PlanetStrategy>) -> Self {
         Self {
-            log_part: Participant::new(Planet, id),
+            log_part,
+            strategy,
+            queue: None,
+            fairness: Fairness::new(FairnessPolicy::Unlimited),
+            sessions: SessionRegistry::default(),
+            economy: None,
+            reservations: None,
+            cells: CellAllocator::new(CellPolicy::default(), CellStatistics::default()),
+            event_log: None,
+            metrics: None,
+            audit: None,
+            defense_reserve: DefenseReserve::default(),
+            risk: AsteroidRisk::default(),
+            wasted_sunrays: 0,
+            failed_rocket_builds: 0,
+            planet_type: PlanetType::C,
+            snapshot_path: None,
+            restore: None,
+            relays: Relays::default(),
+        }
+    }
+
+    /// Park the generation and combination requests that find no charged cell
+    pub fn with_queue(mut self, config: QueueConfig, directory: ExplorerDirectory) -> Self {
+        self.queue = Some(PendingQueue::new(config, directory));
+        self
+    }
+
+    pub(crate) fn with_relays(mut self, relays: Relays) -> Self {
+        self.relays = relays;
+        self
+    }
+
+    /// Share the charged cells between explorers according to a fairness policy
+    pub fn with_fairness(mut self, policy: FairnessPolicy) -> Self {
+        self.fairness = Fairness::new(policy);
+        self
+    }
+
+    /// Record the explorer sessions in a shared registry
+    pub fn with_sessions(mut self, sessions: SessionRegistry) -> Self {
+        self.sessions = sessions;
+        self
+    }
+
+    /// Charge explorers for the resources they get, in exchange for credit
+    pub fn with_economy(mut self, config: EconomyConfig) -> Self {
+        self.economy = Some(Economy::new(config));
+        self
+    }
+
+    /// Hold a cell for every explorer offered one, until `hold_events` planet events after the offer
+    pub fn with_cell_reservations(mut self, hold_events: u32) -> Self {
+        self.reservations = Some(Reservations::new(hold_events));
+        self
+    }
+
+    /// Pick the charged cells to spend with a policy, counting their usage in `statistics`
+    pub fn with_cell_allocation(mut self, policy: CellPolicy, statistics: CellStatistics) -> Self {
+        self.cells = CellAllocator::new(policy, statistics);
+        self
+    }
+
+    /// Capture every logged event in an event log
+    pub fn with_event_log(mut self, event_log: EventLog) -> Self {
+        self.event_log = Some(event_log);
+        self
+    }
+
+    /// Update a metrics registry on every callback
+    pub fn with_metrics(mut self, metrics: PlanetMetrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Audit the resources exchanged through combination requests (debug builds only)
+    pub fn with_conservation_audit(mut self, audit: ConservationAudit) -> Self {
+        self.audit = Some(audit);
+        self
+    }
+
+    /// Keep charged cells for defense when reporting the available ones
+    pub fn with_defense_reserve(mut self, policy: DefenseReserve) -> Self {
+        self.defense_reserve = policy;
+        self
+    }
+
+    /// Estimate the asteroid risk with a custom model
+    pub fn with_risk(mut self, risk: AsteroidRisk) -> Self {
+        self.risk = risk;
+        self
+    }
+
+    /// Type of the planet driven by the AI, type C unless given
+    pub fn with_planet_type(mut self, planet_type: PlanetType) -> Self {
+        self.planet_type = planet_type;
+        self
+    }
+
+    /// Write a snapshot to `path` every time the AI is stopped
+    pub fn with_snapshot_path(mut self, path: PathBuf) -> Self {
+        self.snapshot_path = Some(path);
+        self
+    }
+
+    /// Resume from a snapshot: the counters are restored right away,
+    /// the cells and the rocket as soon as the AI gets the planet state
+    pub fn with_restore(mut self, snapshot: PlanetSnapshot) -> Self {
+        self.wasted_sunrays = snapshot.wasted_sunrays;
+        self.failed_rocket_builds = snapshot.failed_rocket_builds;
+        self.restore = Some(snapshot);
+        self
+    }
+
+    fn snapshot(
+        &self,
+        state: &PlanetState,
+        generator: &Generator,
+        combinator: &Combinator,
+    ) -> PlanetSnapshot {
+        let dummy = state.to_dummy();
+        let mut gen_rules: Vec<_> = generator.all_available_recipes().into_iter().collect();
+        gen_rules.sort_by_key(|rule| format!("{rule:?}"));
+        let mut comb_rules: Vec<_> = combinator.all_available_recipes().into_iter().collect();
+        comb_rules.sort_by_key(|rule| format!("{rule:?}"));
+        PlanetSnapshot {
+            planet_id: self.log_part.id,
+            strategy: self.strategy.name().to_string(),
+            planet_type: format!("{:?}", self.planet_type),
+            gen_rules,
+            comb_rules,
+            energy_cells: dummy.energy_cells,
+            has_rocket: dummy.has_rocket,
+            wasted_sunrays: self.wasted_sunrays,
+            failed_rocket_builds: self.failed_rocket_builds,
+        }
+    }
+
+    /// Replay the snapshot given to `with_restore`, if it wasn't replayed yet
+    fn apply_restore(&mut self, state: &mut PlanetState) {
+        let Some(snapshot) = self.restore.take() else {
+            return;
+        };
+        match snapshot.replay(state) {
+            Ok(()) => self.log(PlanetEvent::SnapshotRestored),
+            Err(reason) => self.log(PlanetEvent::SnapshotFailed { reason }),
         }
     }
+
+    fn can_defer(&self, explorer_id: u32) -> bool {
+        self.fairness.allows(explorer_id)
+            && self
+                .queue
+                .as_ref()
+                .is_some_and(|queue| queue.has_room(explorer_id))
+    }
+
+    fn defer(&mut self, explorer_id: u32, request: Deferred) {
+        if let Some(queue) = self.queue.as_mut() {
+            self.fairness.wait(explorer_id);
+            let kind = request.kind();
+            queue.push(explorer_id, request);
+            self.log(PlanetEvent::RequestQueued {
+                explorer_id,
+                request: kind,
+            });
+        }
+    }
+
+    /// Send a response outside of `handle_explorer_msg`
+    fn deliver(&self, explorer_id: u32, msg: PlanetToExplorer) {
+        if let Some(queue) = self.queue.as_ref()
+            && queue.directory.send(explorer_id, msg).is_err()
+        {
+            self.log(PlanetEvent::ResponseUndeliverable { explorer_id });
+        }
+    }
+
+    /// Serve the parked requests in order while there are charged cells explorers can spend,
+    /// then answer the ones that waited too long with a failure.
+    ///
+    /// The requests of explorers that find every charged cell held for others,
+    /// or that give way to explorers waiting behind them, keep waiting
+    fn serve_pending(
+        &mut self,
+        state: &mut PlanetState,
+        generator: &Generator,
+        combinator: &Combinator,
+    ) {
+        while self.cells.pick(state, CellUse::Explorer).is_some() {
+            let round_robin = self.fairness.policy == FairnessPolicy::RoundRobin;
+            let fairness = &self.fairness;
+            // with the same key for everyone, the first request that can be served is taken
+            let key = |explorer_id| {
+                if round_robin {
+                    fairness.consumed(explorer_id)
+                } else {
+                    0
+                }
+            };
+            let charged = state.cells_iter().filter(|cell| cell.is_charged()).count();
+            let free = self.cells.explorer_cells(state);
+            let reservations = self.reservations.as_ref();
+            let ready = |explorer_id| {
+                !reservations.is_some_and(|r| r.blocks(explorer_id, charged))
+                    && !fairness.gives_way(explorer_id, free)
+            };
+            let Some(pending) = self
+                .queue
+                .as_mut()
+                .and_then(|q| q.pop_min_by_key(key, ready))
+            else {
+                break;
+            };
+            let explorer_id = pending.explorer_id;
+            let response = match pending.request {
+                Deferred::Generate(resource) => {
+                    self.generate_resource(state, generator, explorer_id, resource)
+                }
+                Deferred::Combine(msg, ticket) => {
+                    self.combine_resources(state, combinator, explorer_id, msg, ticket)
+                }
+            };
+            self.deliver(explorer_id, response);
+        }
+
+        let expired = match self.queue.as_mut() {
+            Some(queue) => queue.expire(),
+            None => return,
+        };
+        for pending in expired {
+            let response = self.refuse(pending.explorer_id, pending.request, TIMED_OUT);
+            self.deliver(pending.explorer_id, response);
+        }
+    }
+
+    /// Charge a cell, logging it, and give back the sunray if every cell is already charged
+    fn charge_cell(&self, state: &mut PlanetState, sunray: Sunray) -> Option<Sunray> {
+        let sunray_left = state.charge_cell(sunray);
+        if sunray_left.is_none() {
+            self.log(PlanetEvent::CellCharged);
+        }
+        sunray_left
+    }
+
+    /// Build a rocket from the cell at `index`, logging the outcome
+    fn build_rocket(&mut self, state: &mut PlanetState, index: usize) -> bool {
+        match state.build_rocket(index) {
+            Ok(()) => {
+                self.cells.record(index, CellUse::Rocket);
+                self.log(PlanetEvent::RocketBuilt);
+                true
+            }
+            Err(reason) => {
+                self.failed_rocket_builds += 1;
+                self.log(PlanetEvent::RocketBuildFailed {
+                    reason,
+                    total_failed: self.failed_rocket_builds,
+                });
+                false
+            }
+        }
+    }
+
+    /// Take the rocket, building it first if a charged cell is available, even a held one
+    fn launch_rocket(&mut self, state: &mut PlanetState) -> Option<Rocket> {
+        if !state.has_rocket()
+            && let Some(index) = self.cells.pick(state, CellUse::Rocket)
+            && self.build_rocket(state, index)
+        {
+            self.drop_unbacked_reservations(state);
+        }
+        if state.has_rocket() {
+            self.log(PlanetEvent::RocketLaunched);
+        }
+        state.take_rocket()
+    }
+
+    /// Generate a basic resource for an explorer, unless its cell quota is exhausted
+    fn generate_resource(
+        &mut self,
+        state: &mut PlanetState,
+        generator: &Generator,
+        explorer_id: u32,
+        resource: BasicResourceType,
+    ) -> PlanetToExplorer {
+        if !self.fairness.allows(explorer_id) {
+            return self.refuse(explorer_id, Deferred::Generate(resource), QUOTA_EXCEEDED);
+        }
+        let bought = ResourceType::Basic(resource);
+        if !self.can_afford(explorer_id, bought, &[]) {
+            return self.refuse(explorer_id, Deferred::Generate(resource), NOT_ENOUGH_CREDIT);
+        }
+        if let Some(reason) = self.cells_kept_from(state, explorer_id) {
+            return self.refuse(explorer_id, Deferred::Generate(resource), reason);
+        }
+        let request = ExplorerToPlanetKind::GenerateResourceRequest;
+        match self.try_generate(state, generator, resource) {
+            Ok(basic) => {
+                self.release_reservation(explorer_id);
+                self.charge(explorer_id, bought, &[]);
+                self.fairness.record(explorer_id);
+                self.sessions.record_served(explorer_id);
+                self.log(PlanetEvent::RequestServed {
+                    explorer_id,
+                    request,
+                });
+                GenerateResourceResponse {
+                    resource: Some(basic),
+                }
+            }
+            Err(reason) => {
+                self.log(PlanetEvent::RequestDenied {
+                    explorer_id,
+                    request,
+                    reason,
+                });
+                GenerateResourceResponse { resource: None }
+            }
+        }
+    }
+
+    /// Combine two resources for an explorer, unless its cell quota is exhausted
+    fn combine_resources(
+        &mut self,
+        state: &mut PlanetState,
+        combinator: &Combinator,
+        explorer_id: u32,
+        msg: ComplexResourceRequest,
+        ticket: AuditTicket,
+    ) -> PlanetToExplorer {
+        if !self.fairness.allows(explorer_id) {
+            return self.refuse(explorer_id, Deferred::Combine(msg, ticket), QUOTA_EXCEEDED);
+        }
+        let types = combination_types(&msg);
+        let (bought, delivered) = (ResourceType::Complex(types.0), types.1);
+        if !self.can_afford(explorer_id, bought, &delivered) {
+            return self.refuse(
+                explorer_id,
+                Deferred::Combine(msg, ticket),
+                NOT_ENOUGH_CREDIT,
+            );
+        }
+        if let Some(reason) = self.cells_kept_from(state, explorer_id) {
+            return self.refuse(explorer_id, Deferred::Combine(msg, ticket), reason);
+        }
+        let request = ExplorerToPlanetKind::CombineResourceRequest;
+        let response = self.try_combine(state, combinator, msg);
+        self.settle(ticket, &response);
+        match &response {
+            CombineResourceResponse {
+                complex_response: Ok(_),
+            } => {
+                self.release_reservation(explorer_id);
+                self.charge(explorer_id, bought, &delivered);
+                self.fairness.record(explorer_id);
+                self.sessions.record_served(explorer_id);
+                self.log(PlanetEvent::RequestServed {
+                    explorer_id,
+                    request,
+                });
+            }
+            CombineResourceResponse {
+                complex_response: Err((reason, _, _)),
+            } => self.log(PlanetEvent::RequestDenied {
+                explorer_id,
+                request,
+                reason: reason.clone(),
+            }),
+            _ => {}
+        }
+        response
+    }
+
+    /// Charged cells not held for any explorer
+    fn unreserved_cells(&self, state: &PlanetState) -> usize {
+        let charged = state.cells_iter().filter(|cell| cell.is_charged()).count();
+        let held = self.reservations.as_ref().map_or(0, Reservations::total);
+        charged.saturating_sub(held)
+    }
+
+    /// Whether there are charged cells, but every one of them is held for other explorers
+    fn cells_held_by_others(&self, state: &PlanetState, explorer_id: u32) -> bool {
+        let Some(reservations) = self.reservations.as_ref() else {
+            return false;
+        };
+        let charged = state.cells_iter().filter(|cell| cell.is_charged()).count();
+        reservations.blocks(explorer_id, charged)
+    }
+
+    /// Why the charged cells are kept from an explorer, if they are.
+    /// An explorer that finds no cell for itself starts waiting for one
+    fn cells_kept_from(&mut self, state: &PlanetState, explorer_id: u32) -> Option<&'static str> {
+        let reason = if self.cells_held_by_others(state, explorer_id) {
+            Some(CELLS_RESERVED)
+        } else if self.gives_way(state, explorer_id) {
+            Some(OTHERS_FIRST)
+        } else {
+            None
+        };
+        if reason.is_some() || self.cells.pick(state, CellUse::Explorer).is_none() {
+            self.fairness.wait(explorer_id);
+        }
+        reason
+    }
+
+    /// Whether an explorer has to leave the cells it could spend to explorers waiting behind it
+    fn gives_way(&self, state: &PlanetState, explorer_id: u32) -> bool {
+        self.fairness
+            .gives_way(explorer_id, self.cells.explorer_cells(state))
+    }
+
+    /// Whether a request of an explorer has to wait for a charged cell it can spend
+    fn must_wait(&self, state: &PlanetState, explorer_id: u32) -> bool {
+        self.cells.pick(state, CellUse::Explorer).is_none()
+            || self.cells_held_by_others(state, explorer_id)
+            || self.gives_way(state, explorer_id)
+    }
+
+    fn release_reservation(&mut self, explorer_id: u32) {
+        if let Some(reservations) = self.reservations.as_mut() {
+            reservations.release(explorer_id);
+        }
+    }
+
+    /// Drop the reservations left without a charged cell, logging them
+    fn drop_unbacked_reservations(&mut self, state: &PlanetState) {
+        let charged = state.cells_iter().filter(|cell| cell.is_charged()).count();
+        let dropped = match self.reservations.as_mut() {
+            Some(reservations) => reservations.fit(charged),
+            None => return,
+        };
+        for explorer_id in dropped {
+            self.log(PlanetEvent::ReservationDropped { explorer_id });
+        }
+    }
+
+    /// Age the reservations by one planet event, logging the expired ones
+    fn age_reservations(&mut self) {
+        let expired = match self.reservations.as_mut() {
+            Some(reservations) => reservations.tick(),
+            None => return,
+        };
+        for explorer_id in expired {
+            self.log(PlanetEvent::ReservationExpired { explorer_id });
+        }
+    }
+
+    /// Whether an explorer can buy a resource, delivering some others in exchange
+    fn can_afford(
+        &self,
+        explorer_id: u32,
+        bought: ResourceType,
+        delivered: &[ResourceType],
+    ) -> bool {
+        self.economy
+            .as_ref()
+            .is_none_or(|economy| economy.can_afford(explorer_id, bought, delivered))
+    }
+
+    /// Settle the purchase of a resource in the ledger, if the economy is enabled
+    fn charge(&mut self, explorer_id: u32, bought: ResourceType, delivered: &[ResourceType]) {
+        if let Some(economy) = self.economy.as_mut() {
+            economy.charge(explorer_id, bought, delivered);
+        }
+    }
+
+    /// Answer a message from an explorer that never arrived or already left with a refusal,
+    /// handing back the inputs of a combination
+    fn reject(&self, msg: ExplorerToPlanet) -> PlanetToExplorer {
+        let explorer_id = msg.explorer_id();
+        self.log(PlanetEvent::RequestRejected {
+            explorer_id,
+            request: ExplorerToPlanetKind::from(&msg),
+        });
+        match msg {
+            ExplorerToPlanet::SupportedResourceRequest { .. } => SupportedResourceResponse {
+                resource_list: HashSet::new(),
+            },
+            ExplorerToPlanet::SupportedCombinationRequest { .. } => SupportedCombinationResponse {
+                combination_list: HashSet::new(),
+            },
+            ExplorerToPlanet::GenerateResourceRequest { resource, .. } => {
+                Deferred::Generate(resource).refuse(NOT_ON_PLANET).0
+            }
+            ExplorerToPlanet::CombineResourceRequest { msg, .. } => {
+                let ticket = ConservationAudit::receive(self.audit.as_ref(), explorer_id, &msg);
+                let (response, ticket) = Deferred::Combine(msg, ticket).refuse(NOT_ON_PLANET);
+                if let Some(ticket) = ticket {
+                    self.settle(ticket, &response);
+                }
+                response
+            }
+            ExplorerToPlanet::AvailableEnergyCellRequest { .. } => {
+                AvailableEnergyCellResponse { available_cells: 0 }
+            }
+        }
+    }
+
+    /// Answer a request that won't be served with a failure
+    fn refuse(&self, explorer_id: u32, request: Deferred, reason: &str) -> PlanetToExplorer {
+        self.log(PlanetEvent::RequestDenied {
+            explorer_id,
+            request: request.kind(),
+            reason: reason.to_string(),
+        });
+        let (response, ticket) = request.refuse(reason);
+        if let Some(ticket) = ticket {
+            self.settle(ticket, &response);
+        }
+        response
+    }
+
+    /// Settle a combination response in the conservation audit,
+    /// logging it if it doesn't account for the inputs of its request
+    fn settle(&self, ticket: AuditTicket, response: &PlanetToExplorer) {
+        if let Some(violation) = ticket.settle(response) {
+            self.log(PlanetEvent::ResourcesNotConserved {
+                explorer_id: violation.explorer_id,
+                requested: violation.requested,
+                sent: violation.sent,
+            });
+        }
+    }
+
+    /// Why the allocation policy found no cell for an explorer
+    fn no_cell_reason(&self, state: &PlanetState) -> &'static str {
+        if state.cells_iter().any(|cell| cell.is_charged()) && self.cells.keeps_last(state) {
+            KEPT_FOR_ROCKET
+        } else {
+            NO_CHARGED_CELL
+        }
+    }
+
+    /// Generate a basic resource using the cell picked by the allocation policy
+    fn try_generate(
+        &mut self,
+        state: &mut PlanetState,
+        generator: &Generator,
+        resource: BasicResourceType,
+    ) -> Result<BasicResource, String> {
+        // Attempts to generate unsupported resources fail
+        if !generator.contains(resource) {
+            return Err(format!("there isn't a recipe for {resource:?}"));
+        }
+        let Some(index) = self.cells.pick(state, CellUse::Explorer) else {
+            return Err(self.no_cell_reason(state).to_string());
+        };
+        let basic = generator.try_make(resource, state.cell_mut(index))?;
+        self.cells.record(index, CellUse::Explorer);
+        Ok(basic)
+    }
+
+    /// Combine two resources using the cell picked by the allocation policy,
+    /// handing both inputs back on failure
+    fn try_combine(
+        &mut self,
+        state: &mut PlanetState,
+        combinator: &Combinator,
+        msg: ComplexResourceRequest,
+    ) -> PlanetToExplorer {
+        let (requested, _) = combination_types(&msg);
+        // Attempts to combine unsupported resources fail
+        if !combinator.contains(requested) {
+            let (r1, r2) = combination_inputs(msg);
+            let reason = format!("there isn't a recipe for {requested:?}");
+            return CombineResourceResponse {
+                complex_response: Err((reason, r1, r2)),
+            };
+        }
+        let Some(index) = self.cells.pick(state, CellUse::Explorer) else {
+            let (r1, r2) = combination_inputs(msg);
+            let reason = self.no_cell_reason(state).to_string();
+            return CombineResourceResponse {
+                complex_response: Err((reason, r1, r2)),
+            };
+        };
+        let complex_response = combinator.try_make(msg, state.cell_mut(index));
+        if complex_response.is_ok() {
+            self.cells.record(index, CellUse::Explorer);
+        }
+        CombineResourceResponse { complex_response }
+    }
 }
 
 impl PlanetAI for AI {
     /// Handle a sunray event:
+    /// - Age the cell reservations
     /// - Charge an energy cell
-    /// - If there is no rocket yet, attempt to build one when a full cell is available
+    /// - Serve the explorer requests waiting for a charged cell
+    /// - If there is no rocket yet, ask the strategy whether a charged cell not reserved
+    ///   for an explorer becomes a rocket
+    /// - If the sunray found every cell full, use it to recharge a cell freed by the steps above
     fn handle_sunray(
         &mut self,
         state: &mut PlanetState,
-        _generator: &Generator,
-        _combinator: &Combinator,
+        generator: &Generator,
+        combinator: &Combinator,
         sunray: Sunray,
     ) {
-        match state.charge_cell(sunray) {
-            None => self.log_charge_cell("charged".to_string()),
-            Some(_) => self.log_charge_cell("already charged".to_string()),
+        self.apply_restore(state);
+        self.risk.record_sunray();
+        self.age_reservations();
+        if let Some(metrics) = &self.metrics {
+            metrics.record_sunray(self.log_part.id);
         }
+        let mut sunray_left = self.charge_cell(state, sunray);
 
-        if state.to_dummy().has_rocket == false {
-            match state.full_cell() {
-                None => {}
-                Some((_cell, i)) => {
-                    let _ = state.build_rocket(i);
-                    self.log_build_rocket();
-                }
-            }
+        self.serve_pending(state, generator, combinator);
+        if let Some(sunray) = sunray_left.take() {
+            sunray_left = self.charge_cell(state, sunray);
+        }
+
+        self.strategy.observe_risk(self.risk.estimate());
+        if state.can_have_rocket()
+            && !state.has_rocket()
+            && self.unreserved_cells(state) > 0
+            && self
+                .strategy
+                .should_build_rocket(&state.to_dummy(), sunray_left.is_some())
+            && let Some(i) = self.cells.pick(state, CellUse::Rocket)
+            && self.build_rocket(state, i)
+            && let Some(sunray) = sunray_left.take()
+        {
+            sunray_left = self.charge_cell(state, sunray);
+        }
+
+        if sunray_left.is_some() {
+            self.wasted_sunrays += 1;
+            self.log(PlanetEvent::CellWasted {
+                total_wasted: self.wasted_sunrays,
+            });
         }
     }
 
     /// Handle an asteroid event:
+    /// - Age the cell reservations
     /// - If a rocket already exists, return it
-    /// - Otherwise, try to build a rocket if a charged cell is available
+    /// - Otherwise, try to build a rocket if a charged cell is available,
+    ///   dropping the reservations it leaves without a cell
     fn handle_asteroid(
         &mut self,
         state: &mut PlanetState,
         _generator: &Generator,
         _combinator: &Combinator,
     ) -> Option<Rocket> {
-        if state.has_rocket() {
-            return Some(state.take_rocket()).unwrap();
-        }
-        if let Some((_cell, index)) = state.full_cell() {
-            let build_rocket_result = state.build_rocket(index);
-            self.log_build_rocket();
-            return match build_rocket_result {
-                Ok(_) => Some(state.take_rocket().unwrap()),
-                Err(_) => None,
-            };
+        self.apply_restore(state);
+        self.risk.record_asteroid();
+        self.age_reservations();
+        let rocket = self.launch_rocket(state);
+        if let Some(metrics) = &self.metrics {
+            metrics.record_asteroid(self.log_part.id, rocket.is_some());
         }
-        None
+        rocket
     }
 
     fn handle_internal_state_req(
@@ -81,6 +793,12 @@ impl PlanetAI for AI {
         _generator: &Generator,
         _combinator: &Combinator,
     ) -> DummyPlanetState {
+        self.apply_restore(state);
+        if let Some(economy) = self.economy.as_ref() {
+            self.log(PlanetEvent::LedgerReported {
+                accounts: economy.ledger(),
+            });
+        }
         state.to_dummy()
     }
 
@@ -91,141 +809,76 @@ impl PlanetAI for AI {
         combinator: &Combinator,
         msg: ExplorerToPlanet,
     ) -> Option<PlanetToExplorer> {
+        self.apply_restore(state);
+        // `Planet::run` only forwards the messages of the explorers it admitted:
+        // the AI still checks them against the arrivals and departures it handled
+        if self.sessions.session(msg.explorer_id()).is_none() {
+            return Some(self.reject(msg));
+        }
+        self.sessions.record_request(msg.explorer_id());
         match msg {
-            ExplorerToPlanet::SupportedResourceRequest {
-                explorer_id: _explorer_id,
-            } => Some(SupportedResourceResponse {
-                resource_list: generator.all_available_recipes(),
-            }),
-            ExplorerToPlanet::SupportedCombinationRequest {
-                explorer_id: _explorer_id,
-            } => Some(SupportedCombinationResponse {
-                combination_list: combinator.all_available_recipes(),
-            }),
+            ExplorerToPlanet::SupportedResourceRequest { explorer_id } => {
+                self.log(PlanetEvent::RequestServed {
+                    explorer_id,
+                    request: ExplorerToPlanetKind::SupportedResourceRequest,
+                });
+                Some(SupportedResourceResponse {
+                    resource_list: generator.all_available_recipes(),
+                })
+            }
+            ExplorerToPlanet::SupportedCombinationRequest { explorer_id } => {
+                self.log(PlanetEvent::RequestServed {
+                    explorer_id,
+                    request: ExplorerToPlanetKind::SupportedCombinationRequest,
+                });
+                Some(SupportedCombinationResponse {
+                    combination_list: combinator.all_available_recipes(),
+                })
+            }
             ExplorerToPlanet::GenerateResourceRequest {
-                explorer_id: _explorer_id,
+                explorer_id,
                 resource,
             } => {
-                if let Some((cell, _index)) = state.full_cell() {
-                    return match resource {
-                        BasicResourceType::Silicon => match generator.make_silicon(cell) {
-                            Ok(basic) => Some(GenerateResourceResponse {
-                                resource: Some(BasicResource::Silicon(basic)),
-                            }),
-                            Err(_) => Some(GenerateResourceResponse { resource: None }),
-                        },
-                        // Attempts to generate unsupported resources fail
-                        _ => Some(GenerateResourceResponse { resource: None }),
-                    };
+                // Requests without a recipe can't be served later either
+                if generator.contains(resource)
+                    && self.must_wait(state, explorer_id)
+                    && self.can_defer(explorer_id)
+                {
+                    self.defer(explorer_id, Deferred::Generate(resource));
+                    return None;
                 }
-                // there isn't any charged cell available
-                Some(GenerateResourceResponse { resource: None })
+                Some(self.generate_resource(state, generator, explorer_id, resource))
             }
-            ExplorerToPlanet::CombineResourceRequest {
-                explorer_id: _explorer_id,
-                msg,
-            } => match msg {
-                ComplexResourceRequest::Robot(silicon, life) => {
-                    if let Some((cell, _index)) = state.full_cell() {
-                        return match combinator.make_robot(silicon, life, cell) {
-                            Ok(complex) => Some(CombineResourceResponse {
-                                complex_response: Ok(ComplexResource::Robot(complex)),
-                            }),
-                            Err((err, silicon, life)) => Some(CombineResourceResponse {
-                                complex_response: Err((
-                                    err,
-                                    GenericResource::BasicResources(BasicResource::Silicon(
-                                        silicon,
-                                    )),
-                                    GenericResource::ComplexResources(ComplexResource::Life(life)),
-                                )),
-                            }),
-                        };
-                    }
-                    Some(CombineResourceResponse {
-                        complex_response: Err((
-                            "There isn't any charged cell".to_string(),
-                            GenericResource::BasicResources(BasicResource::Silicon(silicon)),
-                            GenericResource::ComplexResources(ComplexResource::Life(life)),
-                        )),
-                    })
-                }
-                ComplexResourceRequest::Water(hydrogen, oxygen) => Some(CombineResourceResponse {
-                    complex_response: Err((
-                        "there isn't a recipe for water".to_string(),
-                        GenericResource::BasicResources(BasicResource::Hydrogen(hydrogen)),
-                        GenericResource::BasicResources(BasicResource::Oxygen(oxygen)),
-                    )),
-                }),
-                ComplexResourceRequest::Diamond(carbon1, carbon2) => {
-                    if let Some((cell, _index)) = state.full_cell() {
-                        return match combinator.make_diamond(carbon1, carbon2, cell) {
-                            Ok(complex) => Some(CombineResourceResponse {
-                                complex_response: Ok(ComplexResource::Diamond(complex)),
-                            }),
-                            Err((err, carbon1, carbon2)) => Some(CombineResourceResponse {
-                                complex_response: Err((
-                                    err,
-                                    GenericResource::BasicResources(BasicResource::Carbon(carbon1)),
-                                    GenericResource::BasicResources(BasicResource::Carbon(carbon2)),
-                                )),
-                            }),
-                        };
-                    }
-                    Some(CombineResourceResponse {
-                        complex_response: Err((
-                            "There isn't any charged cell".to_string(),
-                            GenericResource::BasicResources(BasicResource::Carbon(carbon1)),
-                            GenericResource::BasicResources(BasicResource::Carbon(carbon2)),
-                        )),
-                    })
+            ExplorerToPlanet::CombineResourceRequest { explorer_id, msg } => {
+                let ticket = ConservationAudit::receive(self.audit.as_ref(), explorer_id, &msg);
+                if combinator.contains(combination_types(&msg).0)
+                    && self.must_wait(state, explorer_id)
+                    && self.can_defer(explorer_id)
+                {
+                    self.defer(explorer_id, Deferred::Combine(msg, ticket));
+                    return None;
                 }
-                ComplexResourceRequest::Life(water, carbon) => Some(CombineResourceResponse {
-                    complex_response: Err((
-                        "there isn't a recipe for life".to_string(),
-                        GenericResource::ComplexResources(ComplexResource::Water(water)),
-                        GenericResource::BasicResources(BasicResource::Carbon(carbon)),
-                    )),
-                }),
-                ComplexResourceRequest::Dolphin(water, life) => Some(CombineResourceResponse {
-                    complex_response: Err((
-                        "there isn't a recipe for dolphin".to_string(),
-                        GenericResource::ComplexResources(ComplexResource::Water(water)),
-                        GenericResource::ComplexResources(ComplexResource::Life(life)),
-                    )),
-                }),
-                ComplexResourceRequest::AIPartner(robot, diamond) => {
-                    if let Some((cell, _index)) = state.full_cell() {
-                        return match combinator.make_aipartner(robot, diamond, cell) {
-                            Ok(complex) => Some(CombineResourceResponse {
-                                complex_response: Ok(ComplexResource::AIPartner(complex)),
-                            }),
-                            Err((err, robot, diamond)) => Some(CombineResourceResponse {
-                                complex_response: Err((
-                                    err,
-                                    GenericResource::ComplexResources(ComplexResource::Robot(
-                                        robot,
-                                    )),
-                                    GenericResource::ComplexResources(ComplexResource::Diamond(
-                                        diamond,
-                                    )),
-                                )),
-                            }),
-                        };
+                Some(self.combine_resources(state, combinator, explorer_id, msg, ticket))
+            }
+            ExplorerToPlanet::AvailableEnergyCellRequest { explorer_id } => {
+                self.log(PlanetEvent::RequestServed {
+                    explorer_id,
+                    request: ExplorerToPlanetKind::AvailableEnergyCellRequest,
+                });
+                let budget = EnergyBudget::new(
+                    &state.to_dummy(),
+                    state.can_have_rocket(),
+                    self.defense_reserve,
+                    self.risk.estimate(),
+                );
+                let mut available_cells = budget.available().min(self.cells.explorer_cells(state));
+                if let Some(reservations) = self.reservations.as_mut() {
+                    available_cells =
+                        available_cells.saturating_sub(reservations.held_by_others(explorer_id));
+                    if available_cells > 0 && reservations.reserve(explorer_id) {
+                        self.log(PlanetEvent::CellReserved { explorer_id });
                     }
-                    Some(CombineResourceResponse {
-                        complex_response: Err((
-                            "There isn't any charged cell".to_string(),
-                            GenericResource::ComplexResources(ComplexResource::Robot(robot)),
-                            GenericResource::ComplexResources(ComplexResource::Diamond(diamond)),
-                        )),
-                    })
                 }
-            },
-            ExplorerToPlanet::AvailableEnergyCellRequest {
-                explorer_id: _explorer_id,
-            } => {
-                let available_cells = state.to_dummy().charged_cells_count;
                 Some(AvailableEnergyCellResponse {
                     available_cells: available_cells as u32,
                 })
@@ -233,27 +886,94 @@ impl PlanetAI for AI {
         }
     }
 
+    /// Handle an explorer arrival:
+    /// - Open its session
+    /// - Open its account with the welcome credit, on its first visit
     fn on_explorer_arrival(
         &mut self,
         _state: &mut PlanetState,
         _generator: &Generator,
         _combinator: &Combinator,
-        _explorer_id: u32,
+        explorer_id: u32,
     ) {
+        self.sessions.open(explorer_id);
+        if let Some(economy) = self.economy.as_mut() {
+            economy.welcome(explorer_id);
+        }
+        self.log(PlanetEvent::ExplorerArrived { explorer_id });
     }
 
+    /// Handle an explorer departure:
+    /// - Close its session, logging the summary of the visit
+    /// - Reset the cells it consumed during the visit and release its reserved cell
+    /// - Answer its parked requests with a failure, handing back the combination inputs
     fn on_explorer_departure(
         &mut self,
         _state: &mut PlanetState,
         _generator: &Generator,
         _combinator: &Combinator,
-        _explorer_id: u32,
+        explorer_id: u32,
     ) {
+        let session = self.sessions.close(explorer_id).unwrap_or_default();
+        self.log(PlanetEvent::ExplorerLeft {
+            explorer_id,
+            requests: session.requests,
+            resources_handed_out: session.resources_handed_out,
+        });
+        self.fairness.reset(explorer_id);
+        self.release_reservation(explorer_id);
+
+        let removed = match self.queue.as_mut() {
+            Some(queue) => queue.remove_explorer(explorer_id),
+            None => return,
+        };
+        for pending in removed {
+            let response = self.refuse(explorer_id, pending.request, EXPLORER_LEFT);
+            self.deliver(explorer_id, response);
+        }
+        if let Some(queue) = self.queue.as_ref() {
+            queue.directory.remove(explorer_id);
+        }
     }
 
     fn on_start(&mut self, _state: &PlanetState, _generator: &Generator, _combinator: &Combinator) {
     }
 
-    fn on_stop(&mut self, _state: &PlanetState, _generator: &Generator, _combinator: &Combinator) {
+    /// Handle the AI being stopped:
+    /// - Write the snapshot, if a path was given and the restored one was applied
+    fn on_stop(&mut self, state: &PlanetState, generator: &Generator, combinator: &Combinator) {
+        let Some(path) = self.snapshot_path.as_ref() else {
+            return;
+        };
+        // The planet state is still the empty start state: keep the checkpoint being restored
+        if self.restore.is_some() {
+            return;
+        }
+        match self.snapshot(state, generator, combinator).save(path) {
+            Ok(()) => self.log(PlanetEvent::SnapshotSaved),
+            Err(err) => self.log(PlanetEvent::SnapshotFailed {
+                reason: err.to_string(),
+            }),
+        }
+    }
+}
+
+/// Split a combination request into its two inputs
+pub(crate) fn combination_inputs(
+    msg: ComplexResourceRequest,
+) -> (GenericResource, GenericResource) {
+    match msg {
+        ComplexResourceRequest::Water(hydrogen, oxygen) => {
+            (hydrogen.to_generic(), oxygen.to_generic())
+        }
+        ComplexResourceRequest::Diamond(carbon1, carbon2) => {
+            (carbon1.to_generic(), carbon2.to_generic())
+        }
+        ComplexResourceRequest::Life(water, carbon) => (water.to_generic(), carbon.to_generic()),
+        ComplexResourceRequest::Robot(silicon, life) => (silicon.to_generic(), life.to_generic()),
+        ComplexResourceRequest::Dolphin(water, life) => (water.to_generic(), life.to_generic()),
+        ComplexResourceRequest::AIPartner(robot, diamond) => {
+            (robot.to_generic(), diamond.to_generic())
+        }
     }
 }
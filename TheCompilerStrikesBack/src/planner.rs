@@ -0,0 +1,170 @@
+use common_game::components::resource::{
+    BasicResourceType, Combinator, ComplexResourceRequest, ComplexResourceType, Generator,
+    GenericResource, ResourceType,
+};
+use std::collections::HashSet;
+
+/*
+   Planner for explorers that want a complex resource from our planet.
+
+   Starting from the target, every input is expanded with the recipe that produces it:
+   basic resources the planet generates and complex resources it combines cost one
+   charged cell each, anything else must be brought by the explorer.
+   The planner only looks at the recipes, not at the charged cells currently available.
+*/
+
+/// Step of a combination plan
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PlanNode {
+    /// Generated by the planet
+    Generate(BasicResourceType),
+    /// Combined by the planet from the two inputs
+    Combine {
+        resource: ComplexResourceType,
+        inputs: Box<(PlanNode, PlanNode)>,
+    },
+    /// Not producible on the planet: the explorer has to bring it
+    Missing(ResourceType),
+}
+
+impl PlanNode {
+    fn cells_required(&self) -> usize {
+        match self {
+            PlanNode::Generate(_) => 1,
+            PlanNode::Combine { inputs, .. } => {
+                1 + inputs.0.cells_required() + inputs.1.cells_required()
+            }
+            PlanNode::Missing(_) => 0,
+        }
+    }
+
+    fn collect_missing(&self, missing: &mut Vec<ResourceType>) {
+        match self {
+            PlanNode::Generate(_) => {}
+            PlanNode::Combine { inputs, .. } => {
+                inputs.0.collect_missing(missing);
+                inputs.1.collect_missing(missing);
+            }
+            PlanNode::Missing(resource) => missing.push(*resource),
+        }
+    }
+}
+
+/// Dependency tree of a complex resource on a planet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CombinationPlan {
+    pub target: ComplexResourceType,
+    pub tree: PlanNode,
+}
+
+impl CombinationPlan {
+    /// Plan a target from the recipes of a planet, as listed by
+    /// `SupportedResourceResponse` and `SupportedCombinationResponse`
+    pub fn new(
+        target: ComplexResourceType,
+        resources: &HashSet<BasicResourceType>,
+        combinations: &HashSet<ComplexResourceType>,
+    ) -> Self {
+        Self {
+            target,
+            tree: expand(ResourceType::Complex(target), resources, combinations),
+        }
+    }
+
+    /// Charged cells the planet spends to produce the target, one per generation or combination
+    pub fn cells_required(&self) -> usize {
+        self.tree.cells_required()
+    }
+
+    /// Inputs the explorer has to bring, once per use
+    pub fn missing_inputs(&self) -> Vec<ResourceType> {
+        let mut missing = Vec::new();
+        self.tree.collect_missing(&mut missing);
+        missing
+    }
+
+    /// True if the planet can produce the target on its own
+    pub fn is_self_sufficient(&self) -> bool {
+        self.missing_inputs().is_empty()
+    }
+}
+
+/// Plan a target from the recipes of a generator and a combinator
+pub fn plan(
+    target: ComplexResourceType,
+    generator: &Generator,
+    combinator: &Combinator,
+) -> CombinationPlan {
+    CombinationPlan::new(
+        target,
+        &generator.all_available_recipes(),
+        &combinator.all_available_recipes(),
+    )
+}
+
+fn expand(
+    resource: ResourceType,
+    resources: &HashSet<BasicResourceType>,
+    combinations: &HashSet<ComplexResourceType>,
+) -> PlanNode {
+    match resource {
+        ResourceType::Basic(basic) if resources.contains(&basic) => PlanNode::Generate(basic),
+        ResourceType::Complex(complex) if combinations.contains(&complex) => {
+            let (lhs, rhs) = recipe_inputs(complex);
+            PlanNode::Combine {
+                resource: complex,
+                inputs: Box::new((
+                    expand(lhs, resources, combinations),
+                    expand(rhs, resources, combinations),
+                )),
+            }
+        }
+        _ => PlanNode::Missing(resource),
+    }
+}
+
+/// Inputs of every combination rule, also used by the conservation audit, the trace replayer
+/// and the simulated explorer
+pub fn recipe_inputs(complex: ComplexResourceType) -> (ResourceType, ResourceType) {
+    use BasicResourceType::{Carbon, Hydrogen, Oxygen, Silicon};
+    use ComplexResourceType::{AIPartner, Diamond, Dolphin, Life, Robot, Water};
+    use ResourceType::{Basic, Complex};
+
+    match complex {
+        Water => (Basic(Hydrogen), Basic(Oxygen)),
+        Diamond => (Basic(Carbon), Basic(Carbon)),
+        Life => (Complex(Water), Basic(Carbon)),
+        Robot => (Basic(Silicon), Complex(Life)),
+        Dolphin => (Complex(Water), Complex(Life)),
+        AIPartner => (Complex(Robot), Complex(Diamond)),
+    }
+}
+
+/// Combination request for a complex resource from inputs of the types given by [`recipe_inputs`],
+/// failing with the reason if an input has another type
+pub fn combination_request(
+    complex: ComplexResourceType,
+    lhs: GenericResource,
+    rhs: GenericResource,
+) -> Result<ComplexResourceRequest, String> {
+    Ok(match complex {
+        ComplexResourceType::Water => {
+            ComplexResourceRequest::Water(lhs.to_hydrogen()?, rhs.to_oxygen()?)
+        }
+        ComplexResourceType::Diamond => {
+            ComplexResourceRequest::Diamond(lhs.to_carbon()?, rhs.to_carbon()?)
+        }
+        ComplexResourceType::Life => {
+            ComplexResourceRequest::Life(lhs.to_water()?, rhs.to_carbon()?)
+        }
+        ComplexResourceType::Robot => {
+            ComplexResourceRequest::Robot(lhs.to_silicon()?, rhs.to_life()?)
+        }
+        ComplexResourceType::Dolphin => {
+            ComplexResourceRequest::Dolphin(lhs.to_water()?, rhs.to_life()?)
+        }
+        ComplexResourceType::AIPartner => {
+            ComplexResourceRequest::AIPartner(lhs.to_robot()?, rhs.to_diamond()?)
+        }
+    })
+}
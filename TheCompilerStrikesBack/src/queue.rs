@@ -0,0 +1,195 @@
+use crate::conservation::AuditTicket;
+use crate::planet_ai::combination_inputs;
+use crate::relay::{Relays, forward};
+use common_game::components::resource::{BasicResourceType, ComplexResourceRequest};
+use common_game::protocols::orchestrator_planet::OrchestratorToPlanet;
+use common_game::protocols::planet_explorer::{ExplorerToPlanetKind, PlanetToExplorer};
+use crossbeam_channel::{Receiver, Sender, unbounded};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+/*
+   Explorer requests that can't be served because no cell is charged are parked here
+   and served on the next sunrays, in arrival order.
+
+   The planet keeps the explorer channels to itself and only lets the AI answer the
+   message it is handling, so a deferred response is sent through an ExplorerDirectory:
+   the orchestrator channel is relayed through a thread that records the sender of
+   every IncomingExplorerRequest before forwarding it to the planet.
+   The relay thread belongs to the AI: it is stopped and joined when the planet drops it.
+*/
+
+/// Configuration of the explorer request queue
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueueConfig {
+    /// Maximum number of pending requests per explorer
+    pub max_len: usize,
+    /// Number of sunrays a request can wait before it is answered with a failure
+    pub timeout_sunrays: u32,
+}
+
+/// Request parked until a cell is charged
+pub(crate) enum Deferred {
+    Generate(BasicResourceType),
+    Combine(ComplexResourceRequest, AuditTicket),
+}
+
+impl Deferred {
+    pub(crate) fn kind(&self) -> ExplorerToPlanetKind {
+        match self {
+            Deferred::Generate(_) => ExplorerToPlanetKind::GenerateResourceRequest,
+            Deferred::Combine(..) => ExplorerToPlanetKind::CombineResourceRequest,
+        }
+    }
+
+    /// Failure response for a request that won't be served, handing back the inputs of a combination,
+    /// with the audit ticket it settles
+    pub(crate) fn refuse(self, reason: &str) -> (PlanetToExplorer, Option<AuditTicket>) {
+        match self {
+            Deferred::Generate(_) => (
+                PlanetToExplorer::GenerateResourceResponse { resource: None },
+                None,
+            ),
+            Deferred::Combine(msg, ticket) => {
+                let (r1, r2) = combination_inputs(msg);
+                let response = PlanetToExplorer::CombineResourceResponse {
+                    complex_response: Err((reason.to_string(), r1, r2)),
+                };
+                (response, Some(ticket))
+            }
+        }
+    }
+}
+
+pub(crate) struct PendingRequest {
+    pub(crate) explorer_id: u32,
+    pub(crate) request: Deferred,
+    pub(crate) age: u32,
+}
+
+pub(crate) struct PendingQueue {
+    pub(crate) config: QueueConfig,
+    pub(crate) directory: ExplorerDirectory,
+    pub(crate) pending: VecDeque<PendingRequest>,
+}
+
+impl PendingQueue {
+    pub(crate) fn new(config: QueueConfig, directory: ExplorerDirectory) -> Self {
+        Self {
+            config,
+            directory,
+            pending: VecDeque::new(),
+        }
+    }
+
+    pub(crate) fn pending_for(&self, explorer_id: u32) -> usize {
+        self.pending
+            .iter()
+            .filter(|p| p.explorer_id == explorer_id)
+            .count()
+    }
+
+    pub(crate) fn has_room(&self, explorer_id: u32) -> bool {
+        self.pending_for(explorer_id) < self.config.max_len
+    }
+
+    pub(crate) fn push(&mut self, explorer_id: u32, request: Deferred) {
+        self.pending.push_back(PendingRequest {
+            explorer_id,
+            request,
+            age: 0,
+        });
+    }
+
+    /// Remove the first pending request of the explorer with the lowest key,
+    /// among the explorers that can be served
+    pub(crate) fn pop_min_by_key(
+        &mut self,
+        key: impl Fn(u32) -> u32,
+        ready: impl Fn(u32) -> bool,
+    ) -> Option<PendingRequest> {
+        let index = self
+            .pending
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| ready(p.explorer_id))
+            .min_by_key(|(i, p)| (key(p.explorer_id), *i))
+            .map(|(i, _)| i)?;
+        self.pending.remove(index)
+    }
+
+    /// Age every pending request by one sunray and remove the expired ones
+    pub(crate) fn expire(&mut self) -> Vec<PendingRequest> {
+        let timeout = self.config.timeout_sunrays;
+        let mut expired = Vec::new();
+        let mut kept = VecDeque::new();
+        for mut p in self.pending.drain(..) {
+            p.age += 1;
+            if p.age >= timeout {
+                expired.push(p);
+            } else {
+                kept.push_back(p);
+            }
+        }
+        self.pending = kept;
+        expired
+    }
+
+    /// Remove every pending request of an explorer
+    pub(crate) fn remove_explorer(&mut self, explorer_id: u32) -> Vec<PendingRequest> {
+        let (removed, kept): (Vec<_>, Vec<_>) = self
+            .pending
+            .drain(..)
+            .partition(|p| p.explorer_id == explorer_id);
+        self.pending = kept.into();
+        removed
+    }
+}
+
+/// Senders of the explorers currently on the planet, shared with the orchestrator relay
+#[derive(Clone, Default)]
+pub struct ExplorerDirectory {
+    senders: Arc<Mutex<HashMap<u32, Sender<PlanetToExplorer>>>>,
+}
+
+impl ExplorerDirectory {
+    pub fn register(&self, explorer_id: u32, sender: Sender<PlanetToExplorer>) {
+        self.senders.lock().unwrap().insert(explorer_id, sender);
+    }
+
+    pub fn remove(&self, explorer_id: u32) {
+        self.senders.lock().unwrap().remove(&explorer_id);
+    }
+
+    /// Send a message to an explorer, returning it if the explorer is unknown or disconnected
+    pub fn send(&self, explorer_id: u32, msg: PlanetToExplorer) -> Result<(), PlanetToExplorer> {
+        match self.senders.lock().unwrap().get(&explorer_id) {
+            Some(sender) => sender.send(msg).map_err(|err| err.0),
+            None => Err(msg),
+        }
+    }
+
+    /// Forward every orchestrator message to the returned receiver,
+    /// registering the explorers that arrive on the planet
+    pub(crate) fn relay(
+        &self,
+        rx_orchestrator: Receiver<OrchestratorToPlanet>,
+        relays: &Relays,
+    ) -> Receiver<OrchestratorToPlanet> {
+        let (tx, rx) = unbounded();
+        let directory = self.clone();
+        relays.spawn(move |stop| {
+            forward(rx_orchestrator, tx, stop, |msg| {
+                if let OrchestratorToPlanet::IncomingExplorerRequest {
+                    explorer_id,
+                    new_sender,
+                } = &msg
+                {
+                    directory.register(*explorer_id, new_sender.clone());
+                }
+                msg
+            })
+        });
+        rx
+    }
+}
@@ -0,0 +1,108 @@
+use crossbeam_channel::{Receiver, Sender, TrySendError, select, unbounded};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+/*
+   Threads relaying the channels of a planet, for the request queue and the trace recorder.
+
+   The planet only lets the AI see its channels through the handlers, so anything that
+   watches or rewrites the traffic sits between the planet and its channels on a thread.
+   The threads belong to the AI: when the planet drops it, they are told to stop and joined,
+   after forwarding what the planet sent last without blocking on a peer that stopped reading.
+*/
+
+/// Threads relaying the channels of a planet, stopped and joined on drop
+pub(crate) struct Relays {
+    /// Never used to send: dropping it disconnects the receivers held by the threads
+    stop: Option<Sender<()>>,
+    spawner: RelaySpawner,
+}
+
+/// Handle spawning relay threads owned by a [`Relays`], for relays that spawn other relays
+#[derive(Clone)]
+pub(crate) struct RelaySpawner {
+    rx_stop: Receiver<()>,
+    threads: Arc<Mutex<Vec<JoinHandle<()>>>>,
+}
+
+impl Default for Relays {
+    fn default() -> Self {
+        let (stop, rx_stop) = unbounded();
+        Self {
+            stop: Some(stop),
+            spawner: RelaySpawner {
+                rx_stop,
+                threads: Arc::default(),
+            },
+        }
+    }
+}
+
+impl Relays {
+    /// Spawn a relay thread, which must return once the given receiver disconnects
+    pub(crate) fn spawn(&self, relay: impl FnOnce(Receiver<()>) + Send + 'static) {
+        self.spawner.spawn(relay);
+    }
+
+    pub(crate) fn spawner(&self) -> RelaySpawner {
+        self.spawner.clone()
+    }
+}
+
+impl RelaySpawner {
+    /// Spawn a relay thread, which must return once the given receiver disconnects
+    pub(crate) fn spawn(&self, relay: impl FnOnce(Receiver<()>) + Send + 'static) {
+        let rx_stop = self.rx_stop.clone();
+        let thread = thread::spawn(move || relay(rx_stop));
+        self.threads.lock().unwrap().push(thread);
+    }
+}
+
+impl Drop for Relays {
+    fn drop(&mut self) {
+        self.stop.take();
+        // a relay still running can spawn another one, which sees the stop right away
+        loop {
+            let Some(thread) = self.spawner.threads.lock().unwrap().pop() else {
+                break;
+            };
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Forward every message of `rx` to `tx`, passed through `observe`,
+/// until either side disconnects or the relays stop
+pub(crate) fn forward<T>(
+    rx: Receiver<T>,
+    tx: Sender<T>,
+    stop: Receiver<()>,
+    mut observe: impl FnMut(T) -> T,
+) {
+    loop {
+        let msg = select! {
+            recv(rx) -> msg => match msg {
+                Ok(msg) => observe(msg),
+                Err(_) => return,
+            },
+            recv(stop) -> _ => break,
+        };
+        match tx.try_send(msg) {
+            Ok(()) => {}
+            Err(TrySendError::Disconnected(_)) => return,
+            // wait for a full peer only until the relays stop
+            Err(TrySendError::Full(msg)) => select! {
+                send(tx, msg) -> sent => if sent.is_err() {
+                    return;
+                },
+                recv(stop) -> _ => break,
+            },
+        }
+    }
+    // the planet is gone: hand over its last messages, such as the answer to KillPlanet
+    for msg in rx.try_iter() {
+        if tx.try_send(observe(msg)).is_err() {
+            return;
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use std::collections::BTreeMap;
+
+/*
+   Charged cells held for explorers.
+
+   An explorer that asks for the available cells and is offered at least one gets a cell
+   reserved for it, so that the cell isn't spent on a rocket or on another explorer before
+   its generation or combination request arrives. The reservation is released when the
+   explorer is served or leaves, and expires `hold_events` planet events (sunrays and asteroids)
+   after the offer: asking again doesn't renew it, so polling can't hold a cell forever.
+   Requests of other explorers that find every charged cell held wait in the request queue,
+   if the planet has one, until a reservation ends.
+   Holds never stop the rocket built to survive an asteroid: when it spends a held cell,
+   the newest reservations are dropped until every one of them has a charged cell again.
+
+   Cells are interchangeable, so a reservation holds a number of cells rather than an index.
+*/
+
+pub(crate) struct Reservations {
+    hold_events: u32,
+    /// Planet events left before the reservation of each explorer expires
+    held: BTreeMap<u32, u32>,
+}
+
+impl Reservations {
+    pub(crate) fn new(hold_events: u32) -> Self {
+        Self {
+            hold_events,
+            held: BTreeMap::new(),
+        }
+    }
+
+    /// Reserve a cell for an explorer that doesn't hold one yet, returning whether it was reserved
+    pub(crate) fn reserve(&mut self, explorer_id: u32) -> bool {
+        if self.holds(explorer_id) {
+            return false;
+        }
+        self.held.insert(explorer_id, self.hold_events);
+        true
+    }
+
+    pub(crate) fn holds(&self, explorer_id: u32) -> bool {
+        self.held.contains_key(&explorer_id)
+    }
+
+    /// Cells held for every explorer
+    pub(crate) fn total(&self) -> usize {
+        self.held.len()
+    }
+
+    /// Cells held for explorers other than `explorer_id`
+    pub(crate) fn held_by_others(&self, explorer_id: u32) -> usize {
+        self.total() - usize::from(self.holds(explorer_id))
+    }
+
+    /// Whether there are charged cells, but every one of them is held for explorers other than `explorer_id`
+    pub(crate) fn blocks(&self, explorer_id: u32, charged: usize) -> bool {
+        charged > 0 && charged <= self.held_by_others(explorer_id)
+    }
+
+    pub(crate) fn release(&mut self, explorer_id: u32) {
+        self.held.remove(&explorer_id);
+    }
+
+    /// Drop the newest reservations until at most `charged` are left, returning their explorers
+    pub(crate) fn fit(&mut self, charged: usize) -> Vec<u32> {
+        let mut dropped = Vec::new();
+        while self.held.len() > charged {
+            let newest = self
+                .held
+                .iter()
+                .max_by_key(|(explorer_id, left)| (**left, **explorer_id))
+                .map(|(explorer_id, _)| *explorer_id);
+            if let Some(explorer_id) = newest {
+                self.held.remove(&explorer_id);
+                dropped.push(explorer_id);
+            }
+        }
+        dropped
+    }
+
+    /// Age every reservation by one planet event, returning the explorers whose reservation expired
+    pub(crate) fn tick(&mut self) -> Vec<u32> {
+        let mut expired = Vec::new();
+        self.held.retain(|explorer_id, left| {
+            *left = left.saturating_sub(1);
+            if *left == 0 {
+                expired.push(*explorer_id);
+            }
+            *left > 0
+        });
+        expired
+    }
+}
@@ -0,0 +1,50 @@
+/*
+   Estimate of how likely the next event is an asteroid.
+
+   Every sunray and asteroid handled by the AI updates an exponentially weighted
+   moving average: an asteroid pulls the estimate towards 1, a sunray towards 0.
+   `smoothing` is the weight of the newest event, so higher values react faster to spikes.
+*/
+
+const DEFAULT_SMOOTHING: f64 = 0.2;
+/// Estimate before any event is observed
+const DEFAULT_PRIOR: f64 = 0.1;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AsteroidRisk {
+    smoothing: f64,
+    estimate: f64,
+}
+
+impl Default for AsteroidRisk {
+    fn default() -> Self {
+        Self::new(DEFAULT_SMOOTHING, DEFAULT_PRIOR)
+    }
+}
+
+impl AsteroidRisk {
+    /// `smoothing` and `prior` are clamped to [0, 1]
+    pub fn new(smoothing: f64, prior: f64) -> Self {
+        Self {
+            smoothing: smoothing.clamp(0.0, 1.0),
+            estimate: prior.clamp(0.0, 1.0),
+        }
+    }
+
+    /// Current estimate, between 0 (only sunrays) and 1 (only asteroids)
+    pub fn estimate(&self) -> f64 {
+        self.estimate
+    }
+
+    pub fn record_sunray(&mut self) {
+        self.observe(0.0);
+    }
+
+    pub fn record_asteroid(&mut self) {
+        self.observe(1.0);
+    }
+
+    fn observe(&mut self, sample: f64) {
+        self.estimate += self.smoothing * (sample - self.estimate);
+    }
+}
@@ -0,0 +1,132 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/*
+   Visits of the explorers to our planet.
+
+   A session is opened when an explorer arrives and closed when it leaves: in between,
+   the AI counts the requests the explorer makes and the resources handed out to it,
+   each of which cost one charged cell. Closed sessions are kept as the summary of the visit,
+   up to the last MAX_CLOSED_SESSIONS so that a long-running planet doesn't grow without bound.
+*/
+
+/// Closed sessions kept by a registry, the oldest are dropped first
+pub const MAX_CLOSED_SESSIONS: usize = 256;
+
+/// Activity of an explorer during one visit
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExplorerSession {
+    pub explorer_id: u32,
+    pub arrived_unix: u64,
+    /// `None` while the explorer is on the planet
+    pub departed_unix: Option<u64>,
+    /// Explorer messages handled by the AI
+    pub requests: u32,
+    /// Resources generated or combined for it, one charged cell each
+    pub resources_handed_out: u32,
+}
+
+impl ExplorerSession {
+    /// Seconds spent on the planet, up to now for an open session
+    pub fn duration_secs(&self) -> u64 {
+        self.departed_unix
+            .unwrap_or_else(now_unix)
+            .saturating_sub(self.arrived_unix)
+    }
+}
+
+#[derive(Debug, Default)]
+struct Sessions {
+    open: BTreeMap<u32, ExplorerSession>,
+    closed: VecDeque<ExplorerSession>,
+}
+
+/// Registry of the explorer sessions of a planet.
+#[derive(Debug, Clone, Default)]
+pub struct SessionRegistry {
+    sessions: Arc<Mutex<Sessions>>,
+}
+
+impl SessionRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Session of an explorer currently on the planet
+    pub fn session(&self, explorer_id: u32) -> Option<ExplorerSession> {
+        self.sessions
+            .lock()
+            .unwrap()
+            .open
+            .get(&explorer_id)
+            .cloned()
+    }
+
+    /// Sessions of the explorers currently on the planet, by explorer id
+    pub fn open_sessions(&self) -> Vec<ExplorerSession> {
+        self.sessions
+            .lock()
+            .unwrap()
+            .open
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    /// Last sessions of the explorers that left the planet, oldest first
+    pub fn closed_sessions(&self) -> Vec<ExplorerSession> {
+        self.sessions
+            .lock()
+            .unwrap()
+            .closed
+            .iter()
+            .cloned()
+            .collect()
+    }
+
+    /// Start the session of an arriving explorer, replacing the one still open, if any
+    pub(crate) fn open(&self, explorer_id: u32) {
+        self.sessions.lock().unwrap().open.insert(
+            explorer_id,
+            ExplorerSession {
+                explorer_id,
+                arrived_unix: now_unix(),
+                ..ExplorerSession::default()
+            },
+        );
+    }
+
+    /// Close the session of a departing explorer, returning its summary
+    pub(crate) fn close(&self, explorer_id: u32) -> Option<ExplorerSession> {
+        let mut sessions = self.sessions.lock().unwrap();
+        let mut session = sessions.open.remove(&explorer_id)?;
+        session.departed_unix = Some(now_unix());
+        if sessions.closed.len() == MAX_CLOSED_SESSIONS {
+            sessions.closed.pop_front();
+        }
+        sessions.closed.push_back(session.clone());
+        Some(session)
+    }
+
+    fn update(&self, explorer_id: u32, update: impl FnOnce(&mut ExplorerSession)) {
+        if let Some(session) = self.sessions.lock().unwrap().open.get_mut(&explorer_id) {
+            update(session);
+        }
+    }
+
+    pub(crate) fn record_request(&self, explorer_id: u32) {
+        self.update(explorer_id, |s| s.requests += 1);
+    }
+
+    /// Count a served generation or combination
+    pub(crate) fn record_served(&self, explorer_id: u32) {
+        self.update(explorer_id, |s| s.resources_handed_out += 1);
+    }
+}
+
+fn now_unix() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
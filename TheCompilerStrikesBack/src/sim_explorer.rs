@@ -0,0 +1,263 @@
+use crate::harness::{ExplorerHandle, HarnessError, PlanetHarness};
+use crate::planner::{combination_request, recipe_inputs};
+use common_game::components::resource::{
+    BasicResourceType, ComplexResourceType, GenericResource, ResourceType,
+};
+use common_game::protocols::planet_explorer::{ExplorerToPlanet, PlanetToExplorer};
+use std::collections::HashSet;
+
+/*
+   Scripted explorer used to test trade sessions end to end.
+
+   The explorer arrives on a planet of a PlanetHarness, asks what the planet supports and
+   then works through a shopping list: every goal is either generated or combined, and the
+   inputs of a combination are taken from the inventory or obtained first (recursively).
+   Resources handed back by a failed combination go back to the inventory.
+*/
+
+/// Item of a shopping list
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Goal {
+    Generate(BasicResourceType),
+    Build(ComplexResourceType),
+}
+
+impl Goal {
+    fn resource_type(&self) -> ResourceType {
+        match self {
+            Goal::Generate(basic) => ResourceType::Basic(*basic),
+            Goal::Build(complex) => ResourceType::Complex(*complex),
+        }
+    }
+}
+
+/// A request that didn't produce the wanted resource
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Failure {
+    pub resource: ResourceType,
+    pub reason: String,
+}
+
+/// Outcome of a shopping list
+#[derive(Debug, Default)]
+pub struct TradeReport {
+    pub supported_resources: HashSet<BasicResourceType>,
+    pub supported_combinations: HashSet<ComplexResourceType>,
+    /// Goals of the shopping list that were obtained
+    pub obtained: Vec<ResourceType>,
+    /// Every failed request, intermediate steps included
+    pub failures: Vec<Failure>,
+}
+
+pub struct SimExplorer {
+    explorer_id: u32,
+    inventory: Vec<GenericResource>,
+    endpoint: Option<ExplorerHandle>,
+}
+
+impl SimExplorer {
+    pub fn new(explorer_id: u32) -> Self {
+        Self {
+            explorer_id,
+            inventory: Vec::new(),
+            endpoint: None,
+        }
+    }
+
+    /// Start with resources obtained somewhere else
+    pub fn with_inventory(mut self, inventory: Vec<GenericResource>) -> Self {
+        self.inventory = inventory;
+        self
+    }
+
+    pub fn explorer_id(&self) -> u32 {
+        self.explorer_id
+    }
+
+    pub fn inventory(&self) -> &[GenericResource] {
+        &self.inventory
+    }
+
+    /// Number of resources of a type in the inventory
+    pub fn count(&self, resource: ResourceType) -> usize {
+        self.inventory
+            .iter()
+            .filter(|r| r.get_type() == resource)
+            .count()
+    }
+
+    pub fn take_inventory(&mut self) -> Vec<GenericResource> {
+        std::mem::take(&mut self.inventory)
+    }
+
+    /// Arrive on the planet, work through the shopping list and leave
+    pub fn visit(
+        &mut self,
+        harness: &PlanetHarness,
+        shopping_list: &[Goal],
+    ) -> Result<TradeReport, HarnessError> {
+        self.arrive(harness)?;
+        let report = self.run(shopping_list);
+        self.leave(harness)?;
+        report
+    }
+
+    pub fn arrive(&mut self, harness: &PlanetHarness) -> Result<(), HarnessError> {
+        self.endpoint = Some(harness.attach_explorer(self.explorer_id)?);
+        Ok(())
+    }
+
+    pub fn leave(&mut self, harness: &PlanetHarness) -> Result<(), HarnessError> {
+        self.endpoint = None;
+        harness.detach_explorer(self.explorer_id)
+    }
+
+    /// Work through the shopping list on the planet the explorer arrived on
+    pub fn run(&mut self, shopping_list: &[Goal]) -> Result<TradeReport, HarnessError> {
+        let mut report = TradeReport::default();
+
+        match self.request(ExplorerToPlanet::SupportedResourceRequest {
+            explorer_id: self.explorer_id,
+        })? {
+            PlanetToExplorer::SupportedResourceResponse { resource_list } => {
+                report.supported_resources = resource_list
+            }
+            other => return Err(HarnessError::UnexpectedExplorerResponse(other)),
+        }
+        match self.request(ExplorerToPlanet::SupportedCombinationRequest {
+            explorer_id: self.explorer_id,
+        })? {
+            PlanetToExplorer::SupportedCombinationResponse { combination_list } => {
+                report.supported_combinations = combination_list
+            }
+            other => return Err(HarnessError::UnexpectedExplorerResponse(other)),
+        }
+
+        for goal in shopping_list {
+            if self.obtain(goal.resource_type(), &mut report)? {
+                report.obtained.push(goal.resource_type());
+            }
+        }
+        Ok(report)
+    }
+
+    fn request(&self, msg: ExplorerToPlanet) -> Result<PlanetToExplorer, HarnessError> {
+        match &self.endpoint {
+            Some(endpoint) => endpoint.request(msg),
+            None => Err(HarnessError::Disconnected),
+        }
+    }
+
+    fn take(&mut self, resource: ResourceType) -> Option<GenericResource> {
+        let index = self
+            .inventory
+            .iter()
+            .position(|r| r.get_type() == resource)?;
+        Some(self.inventory.remove(index))
+    }
+
+    /// Take an input from the inventory, obtaining it first if it's missing
+    fn take_or_obtain(
+        &mut self,
+        resource: ResourceType,
+        report: &mut TradeReport,
+    ) -> Result<Option<GenericResource>, HarnessError> {
+        if let Some(found) = self.take(resource) {
+            return Ok(Some(found));
+        }
+        if self.obtain(resource, report)? {
+            return Ok(self.take(resource));
+        }
+        Ok(None)
+    }
+
+    /// Obtain a new resource and put it in the inventory
+    fn obtain(
+        &mut self,
+        resource: ResourceType,
+        report: &mut TradeReport,
+    ) -> Result<bool, HarnessError> {
+        let reason = match resource {
+            ResourceType::Basic(basic) => self.generate(basic, report)?,
+            ResourceType::Complex(complex) => self.build(complex, report)?,
+        };
+        match reason {
+            None => Ok(true),
+            Some(reason) => {
+                report.failures.push(Failure { resource, reason });
+                Ok(false)
+            }
+        }
+    }
+
+    /// Returns the reason of the failure, if any
+    fn generate(
+        &mut self,
+        basic: BasicResourceType,
+        report: &TradeReport,
+    ) -> Result<Option<String>, HarnessError> {
+        if !report.supported_resources.contains(&basic) {
+            return Ok(Some("not supported by the planet".to_string()));
+        }
+        match self.request(ExplorerToPlanet::GenerateResourceRequest {
+            explorer_id: self.explorer_id,
+            resource: basic,
+        })? {
+            PlanetToExplorer::GenerateResourceResponse {
+                resource: Some(resource),
+            } => {
+                self.inventory
+                    .push(GenericResource::BasicResources(resource));
+                Ok(None)
+            }
+            PlanetToExplorer::GenerateResourceResponse { resource: None } => {
+                Ok(Some("generation refused".to_string()))
+            }
+            other => Err(HarnessError::UnexpectedExplorerResponse(other)),
+        }
+    }
+
+    /// Returns the reason of the failure, if any
+    fn build(
+        &mut self,
+        complex: ComplexResourceType,
+        report: &mut TradeReport,
+    ) -> Result<Option<String>, HarnessError> {
+        if !report.supported_combinations.contains(&complex) {
+            return Ok(Some("not supported by the planet".to_string()));
+        }
+        let (lhs, rhs) = recipe_inputs(complex);
+        let Some(r1) = self.take_or_obtain(lhs, report)? else {
+            return Ok(Some(format!("missing input {lhs:?}")));
+        };
+        let Some(r2) = self.take_or_obtain(rhs, report)? else {
+            self.inventory.push(r1);
+            return Ok(Some(format!("missing input {rhs:?}")));
+        };
+
+        let msg = match combination_request(complex, r1, r2) {
+            Ok(msg) => msg,
+            Err(reason) => return Ok(Some(reason)),
+        };
+        match self.request(ExplorerToPlanet::CombineResourceRequest {
+            explorer_id: self.explorer_id,
+            msg,
+        })? {
+            PlanetToExplorer::CombineResourceResponse {
+                complex_response: Ok(resource),
+            } => {
+                self.inventory
+                    .push(GenericResource::ComplexResources(resource));
+                Ok(None)
+            }
+            PlanetToExplorer::CombineResourceResponse {
+                complex_response: Err((reason, r1, r2)),
+            } => {
+                self.inventory.push(r1);
+                self.inventory.push(r2);
+                Ok(Some(reason))
+            }
+            other => Err(HarnessError::UnexpectedExplorerResponse(other)),
+        }
+    }
+}
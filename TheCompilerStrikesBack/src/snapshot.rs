@@ -0,0 +1,97 @@
+use crate::names::{from_names, to_names};
+use common_game::components::planet::{DummyPlanetState, PlanetState, PlanetType};
+use common_game::components::resource::{BasicResourceType, ComplexResourceType};
+use common_game::components::sunray::Sunray;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::Path;
+
+/*
+   Checkpoint of a planet, written as JSON when its AI is stopped.
+
+   The charged cells and the rocket can't be set directly on a new planet, so the
+   restored AI replays them with fresh sunrays the first time it gets the planet state
+   (rocket first, so that the cell it consumes can be charged again).
+   The planet type and its rules aren't serializable, they are written by name.
+*/
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlanetSnapshot {
+    pub planet_id: u32,
+    /// Name of the strategy, see [`crate::strategy::by_name`]
+    pub strategy: String,
+    /// Name of the planet type, from "A" to "D"
+    pub planet_type: String,
+    #[serde(serialize_with = "to_names", deserialize_with = "from_names")]
+    pub gen_rules: Vec<BasicResourceType>,
+    #[serde(serialize_with = "to_names", deserialize_with = "from_names")]
+    pub comb_rules: Vec<ComplexResourceType>,
+    /// Charge of every energy cell, in order
+    pub energy_cells: Vec<bool>,
+    pub has_rocket: bool,
+    pub wasted_sunrays: u32,
+    pub failed_rocket_builds: u32,
+}
+
+impl PlanetSnapshot {
+    /// Planet type named in the snapshot, if the name is valid
+    pub fn planet_type(&self) -> Option<PlanetType> {
+        match self.planet_type.as_str() {
+            "A" => Some(PlanetType::A),
+            "B" => Some(PlanetType::B),
+            "C" => Some(PlanetType::C),
+            "D" => Some(PlanetType::D),
+            _ => None,
+        }
+    }
+
+    pub fn charged_cells_count(&self) -> usize {
+        self.energy_cells.iter().filter(|charged| **charged).count()
+    }
+
+    /// State of the planet as returned by an internal state request
+    pub fn to_dummy(&self) -> DummyPlanetState {
+        DummyPlanetState {
+            energy_cells: self.energy_cells.clone(),
+            charged_cells_count: self.charged_cells_count(),
+            has_rocket: self.has_rocket,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("a snapshot is always serializable")
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        std::fs::write(path, self.to_json())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self::from_json(&std::fs::read_to_string(path)?)?)
+    }
+
+    /// Bring a new planet to the snapshot state using sunrays
+    pub(crate) fn replay(&self, state: &mut PlanetState) -> Result<(), String> {
+        if self.has_rocket && !state.has_rocket() {
+            if state.charge_cell(Sunray::default()).is_some() {
+                return Err("there isn't an empty cell to build the rocket".to_string());
+            }
+            if let Some((_cell, index)) = state.full_cell() {
+                state.build_rocket(index)?;
+            }
+        }
+        for (index, charged) in self.energy_cells.iter().enumerate() {
+            if index >= state.cells_count() {
+                return Err(format!("the planet has only {} cells", state.cells_count()));
+            }
+            if *charged && !state.cell(index).is_charged() {
+                state.cell_mut(index).charge(Sunray::default());
+            }
+        }
+        Ok(())
+    }
+}
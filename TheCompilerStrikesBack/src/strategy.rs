@@ -0,0 +1,124 @@
+use common_game::components::planet::{DummyPlanetState, PlanetType};
+
+/*
+   A strategy decides what happens to the energy of our planet after a sunray:
+   either a charged cell is turned into a rocket (defense) or it is kept
+   charged so that explorers can use it for generation and combination requests.
+
+   The AI asks the strategy only when the planet has no rocket yet, after telling it
+   the current asteroid risk estimate.
+*/
+
+pub trait PlanetStrategy: Send {
+    /// Name of the strategy, used in logs
+    fn name(&self) -> &'static str;
+
+    /// Decide whether a charged cell should be spent to build a rocket:
+    /// - `state` is the planet state right after the sunray was handled
+    /// - `sunray_left` is true when the sunray could not charge any cell (all cells were full)
+    fn should_build_rocket(&mut self, state: &DummyPlanetState, sunray_left: bool) -> bool;
+
+    /// Latest asteroid risk estimate, see [`crate::risk::AsteroidRisk`]
+    fn observe_risk(&mut self, _risk: f64) {}
+}
+
+/// Strategy with the given name, as returned by [`PlanetStrategy::name`]
+pub fn by_name(name: &str) -> Option<Box<dyn PlanetStrategy>> {
+    match name {
+        "defensive" => Some(Box::new(Defensive)),
+        "economic" => Some(Box::new(Economic)),
+        "balanced" => Some(Box::new(Balanced)),
+        "adaptive" => Some(Box::new(Adaptive::default())),
+        _ => None,
+    }
+}
+
+/// Strategy of a planet created without one: types that can have a rocket defend themselves,
+/// the others keep their cells for explorers
+pub fn default_for(planet_type: PlanetType) -> Box<dyn PlanetStrategy> {
+    match planet_type {
+        PlanetType::A | PlanetType::C => Box::new(Defensive),
+        PlanetType::B | PlanetType::D => Box::new(Economic),
+    }
+}
+
+/// Always builds a rocket as soon as a charged cell is available
+pub struct Defensive;
+
+/// Never builds a rocket in advance: cells are kept for explorers and a rocket
+/// is built only when an asteroid arrives and a charged cell is still available
+pub struct Economic;
+
+/// Builds a rocket only when every cell is charged and the incoming sunray
+/// would be wasted, so the freed cell can be recharged right away
+pub struct Balanced;
+
+/// Behaves like [`Defensive`] while the asteroid risk is at least `threshold`
+/// and like [`Balanced`] otherwise
+pub struct Adaptive {
+    pub threshold: f64,
+    risk: f64,
+}
+
+impl Adaptive {
+    pub fn new(threshold: f64) -> Self {
+        Self {
+            threshold,
+            risk: 0.0,
+        }
+    }
+}
+
+impl Default for Adaptive {
+    fn default() -> Self {
+        Self::new(0.3)
+    }
+}
+
+impl PlanetStrategy for Defensive {
+    fn name(&self) -> &'static str {
+        "defensive"
+    }
+
+    fn should_build_rocket(&mut self, state: &DummyPlanetState, _sunray_left: bool) -> bool {
+        state.charged_cells_count > 0
+    }
+}
+
+impl PlanetStrategy for Economic {
+    fn name(&self) -> &'static str {
+        "economic"
+    }
+
+    fn should_build_rocket(&mut self, _state: &DummyPlanetState, _sunray_left: bool) -> bool {
+        false
+    }
+}
+
+impl PlanetStrategy for Balanced {
+    fn name(&self) -> &'static str {
+        "balanced"
+    }
+
+    fn should_build_rocket(&mut self, state: &DummyPlanetState, sunray_left: bool) -> bool {
+        sunray_left && state.charged_cells_count == state.energy_cells.len()
+    }
+}
+
+impl PlanetStrategy for Adaptive {
+    fn name(&self) -> &'static str {
+        "adaptive"
+    }
+
+    fn should_build_rocket(&mut self, state: &DummyPlanetState, sunray_left: bool) -> bool {
+        if self.risk >= self.threshold {
+            Defensive.should_build_rocket(state, sunray_left)
+        } else {
+            Balanced.should_build_rocket(state, sunray_left)
+        }
+    }
+
+    fn observe_risk(&mut self, risk: f64) {
+        self.risk = risk;
+    }
+}
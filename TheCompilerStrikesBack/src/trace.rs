@@ -0,0 +1,565 @@
+use crate::conservation::combination_types;
+use crate::names::{Named, resource_from_name, resource_name};
+use crate::planet_ai::AI;
+use crate::planner::{combination_request, recipe_inputs};
+use crate::relay::{RelaySpawner, Relays, forward};
+use crate::strategy::Defensive;
+use common_game::components::asteroid::Asteroid;
+use common_game::components::energy_cell::EnergyCell;
+use common_game::components::planet::{Planet, PlanetType};
+use common_game::components::resource::{
+    BasicResourceType, ComplexResourceType, GenericResource, ResourceType,
+};
+use common_game::components::sunray::Sunray;
+use common_game::logging::{ActorType, Participant};
+use common_game::protocols::orchestrator_planet::{OrchestratorToPlanet, PlanetToOrchestrator};
+use common_game::protocols::planet_explorer::{ExplorerToPlanet, PlanetToExplorer};
+use crossbeam_channel::{Receiver, Sender, unbounded};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/*
+   Record-and-replay of the traffic of a planet.
+
+   The recorder sits between the planet and its channels: relay threads write every
+   inbound message and every reply to a trace file, one JSON entry per line, and forward
+   it unchanged. The explorer replies are captured by swapping the sender carried by
+   IncomingExplorerRequest with a recording one. The relays run on separate threads, so
+   every entry is stamped from a counter shared by all of them when its message is seen,
+   and the trace is read back in that order. Like the request queue relay, they belong to
+   the AI and are stopped and joined when the planet drops it.
+
+   The replayer feeds the inbound messages of a trace to a fresh planet, one at a time,
+   and compares the replies with the recorded ones. Combination requests are recorded with
+   the types of their inputs: resources can only be made by a planet, so the replayer makes
+   them again with the generator and combinator of two planets it never runs.
+   Replies are matched by order, so the trace should come from a driver that
+   waits for each reply before sending the next message, like the testing harness.
+*/
+
+const REPLAY_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Who sent a traced message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Direction {
+    FromOrchestrator,
+    ToOrchestrator,
+    FromExplorer,
+    ToExplorer,
+}
+
+/// Inbound message that can be sent again to a planet
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Replayable {
+    StartPlanetAI,
+    StopPlanetAI,
+    KillPlanet,
+    Sunray,
+    Asteroid,
+    InternalStateRequest,
+    IncomingExplorer {
+        explorer_id: u32,
+    },
+    OutgoingExplorer {
+        explorer_id: u32,
+    },
+    SupportedResources {
+        explorer_id: u32,
+    },
+    SupportedCombinations {
+        explorer_id: u32,
+    },
+    /// `resource` is the name of a `BasicResourceType`
+    Generate {
+        explorer_id: u32,
+        resource: String,
+    },
+    AvailableEnergyCell {
+        explorer_id: u32,
+    },
+    /// `resource` and `inputs` are the names of the requested and given resource types
+    Combine {
+        explorer_id: u32,
+        resource: String,
+        inputs: [String; 2],
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TraceEntry {
+    /// Order in which the recorder saw the message, across all the channels
+    pub seq: u64,
+    pub timestamp_micros: u64,
+    pub direction: Direction,
+    /// Explorer on the other end, for explorer traffic
+    pub explorer_id: Option<u32>,
+    /// Debug representation of the message
+    pub message: String,
+    /// How to send the message again, for the inbound ones that can be rebuilt
+    pub replay: Option<Replayable>,
+}
+
+impl TraceEntry {
+    fn new(seq: u64, direction: Direction, explorer_id: Option<u32>, message: String) -> Self {
+        let timestamp_micros = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_micros() as u64);
+        Self {
+            seq,
+            timestamp_micros,
+            direction,
+            explorer_id,
+            message,
+            replay: None,
+        }
+    }
+
+    fn is_inbound(&self) -> bool {
+        matches!(
+            self.direction,
+            Direction::FromOrchestrator | Direction::FromExplorer
+        )
+    }
+}
+
+/// Writes the traffic of a planet to a trace file.
+///
+/// Clones share the same file and sequence counter.
+#[derive(Debug, Clone)]
+pub struct TraceRecorder {
+    sink: Arc<Mutex<BufWriter<File>>>,
+    seq: Arc<AtomicU64>,
+}
+
+impl TraceRecorder {
+    /// Record to a new trace file, replacing an existing one
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self {
+            sink: Arc::new(Mutex::new(BufWriter::new(File::create(path)?))),
+            seq: Arc::new(AtomicU64::new(0)),
+        })
+    }
+
+    /// Entry stamped with the next sequence number
+    fn entry(&self, direction: Direction, explorer_id: Option<u32>, message: String) -> TraceEntry {
+        let seq = self.seq.fetch_add(1, Ordering::SeqCst);
+        TraceEntry::new(seq, direction, explorer_id, message)
+    }
+
+    fn write(&self, entry: &TraceEntry) {
+        let line = serde_json::to_string(entry).expect("a trace entry is always serializable");
+        let mut sink = self.sink.lock().unwrap();
+        // entries are flushed one by one so that a crashing session leaves a usable trace
+        if let Err(err) = writeln!(sink, "{line}").and_then(|_| sink.flush()) {
+            log::warn!("trace entry lost: {err}");
+        }
+    }
+
+    /// Relay the orchestrator channels of a planet through the recorder
+    pub(crate) fn wrap_orchestrator(
+        &self,
+        rx_orchestrator: Receiver<OrchestratorToPlanet>,
+        tx_orchestrator: Sender<PlanetToOrchestrator>,
+        relays: &Relays,
+    ) -> (Receiver<OrchestratorToPlanet>, Sender<PlanetToOrchestrator>) {
+        let (tx_in, rx_in) = unbounded();
+        let recorder = self.clone();
+        let spawner = relays.spawner();
+        relays.spawn(move |stop| {
+            forward(rx_orchestrator, tx_in, stop, |msg| {
+                let mut entry =
+                    recorder.entry(Direction::FromOrchestrator, None, format!("{msg:?}"));
+                entry.replay = replayable_orchestrator(&msg);
+                recorder.write(&entry);
+
+                match msg {
+                    OrchestratorToPlanet::IncomingExplorerRequest {
+                        explorer_id,
+                        new_sender,
+                    } => OrchestratorToPlanet::IncomingExplorerRequest {
+                        explorer_id,
+                        new_sender: recorder.wrap_explorer_sender(
+                            explorer_id,
+                            new_sender,
+                            &spawner,
+                        ),
+                    },
+                    msg => msg,
+                }
+            })
+        });
+
+        let (tx_out, rx_out) = unbounded::<PlanetToOrchestrator>();
+        let recorder = self.clone();
+        relays.spawn(move |stop| {
+            forward(rx_out, tx_orchestrator, stop, |msg| {
+                recorder.write(&recorder.entry(
+                    Direction::ToOrchestrator,
+                    None,
+                    format!("{msg:?}"),
+                ));
+                msg
+            })
+        });
+
+        (rx_in, tx_out)
+    }
+
+    /// Relay the explorer channel of a planet through the recorder
+    pub(crate) fn wrap_explorer(
+        &self,
+        rx_explorer: Receiver<ExplorerToPlanet>,
+        relays: &Relays,
+    ) -> Receiver<ExplorerToPlanet> {
+        let (tx, rx) = unbounded();
+        let recorder = self.clone();
+        relays.spawn(move |stop| {
+            forward(rx_explorer, tx, stop, |msg| {
+                let mut entry = recorder.entry(
+                    Direction::FromExplorer,
+                    Some(msg.explorer_id()),
+                    format!("{msg:?}"),
+                );
+                entry.replay = replayable_explorer(&msg);
+                recorder.write(&entry);
+                msg
+            })
+        });
+        rx
+    }
+
+    fn wrap_explorer_sender(
+        &self,
+        explorer_id: u32,
+        sender: Sender<PlanetToExplorer>,
+        spawner: &RelaySpawner,
+    ) -> Sender<PlanetToExplorer> {
+        let (tx, rx) = unbounded::<PlanetToExplorer>();
+        let recorder = self.clone();
+        spawner.spawn(move |stop| {
+            forward(rx, sender, stop, |msg| {
+                recorder.write(&recorder.entry(
+                    Direction::ToExplorer,
+                    Some(explorer_id),
+                    format!("{msg:?}"),
+                ));
+                msg
+            })
+        });
+        tx
+    }
+}
+
+fn replayable_orchestrator(msg: &OrchestratorToPlanet) -> Option<Replayable> {
+    Some(match msg {
+        OrchestratorToPlanet::StartPlanetAI => Replayable::StartPlanetAI,
+        OrchestratorToPlanet::StopPlanetAI => Replayable::StopPlanetAI,
+        OrchestratorToPlanet::KillPlanet => Replayable::KillPlanet,
+        OrchestratorToPlanet::Sunray(_) => Replayable::Sunray,
+        OrchestratorToPlanet::Asteroid(_) => Replayable::Asteroid,
+        OrchestratorToPlanet::InternalStateRequest => Replayable::InternalStateRequest,
+        OrchestratorToPlanet::IncomingExplorerRequest { explorer_id, .. } => {
+            Replayable::IncomingExplorer {
+                explorer_id: *explorer_id,
+            }
+        }
+        OrchestratorToPlanet::OutgoingExplorerRequest { explorer_id } => {
+            Replayable::OutgoingExplorer {
+                explorer_id: *explorer_id,
+            }
+        }
+    })
+}
+
+fn replayable_explorer(msg: &ExplorerToPlanet) -> Option<Replayable> {
+    Some(match msg {
+        ExplorerToPlanet::SupportedResourceRequest { explorer_id } => {
+            Replayable::SupportedResources {
+                explorer_id: *explorer_id,
+            }
+        }
+        ExplorerToPlanet::SupportedCombinationRequest { explorer_id } => {
+            Replayable::SupportedCombinations {
+                explorer_id: *explorer_id,
+            }
+        }
+        ExplorerToPlanet::GenerateResourceRequest {
+            explorer_id,
+            resource,
+        } => Replayable::Generate {
+            explorer_id: *explorer_id,
+            resource: format!("{resource:?}"),
+        },
+        ExplorerToPlanet::AvailableEnergyCellRequest { explorer_id } => {
+            Replayable::AvailableEnergyCell {
+                explorer_id: *explorer_id,
+            }
+        }
+        ExplorerToPlanet::CombineResourceRequest { explorer_id, msg } => {
+            let (resource, inputs) = combination_types(msg);
+            Replayable::Combine {
+                explorer_id: *explorer_id,
+                resource: resource.name(),
+                inputs: inputs.map(resource_name),
+            }
+        }
+    })
+}
+
+/// Read a trace file written by a [`TraceRecorder`], in the order the messages were seen
+pub fn load_trace(path: impl AsRef<Path>) -> io::Result<Vec<TraceEntry>> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut entries = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        if !line.trim().is_empty() {
+            entries.push(serde_json::from_str(&line)?);
+        }
+    }
+    entries.sort_by_key(|entry: &TraceEntry| entry.seq);
+    Ok(entries)
+}
+
+/// Recorded reply that differs from the one of the replayed planet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceDiff {
+    /// Position of the recorded reply in the trace
+    pub entry: usize,
+    pub expected: String,
+    /// `None` if the replayed planet didn't answer
+    pub actual: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct ReplayReport {
+    /// Inbound messages sent again to the planet
+    pub replayed: usize,
+    /// Inbound messages that couldn't be rebuilt
+    pub skipped: usize,
+    pub diffs: Vec<TraceDiff>,
+}
+
+impl ReplayReport {
+    /// True when the replayed planet answered exactly like the recorded one
+    pub fn is_clean(&self) -> bool {
+        self.diffs.is_empty()
+    }
+}
+
+/// Feed the inbound messages of a trace to the planet returned by `create`
+/// and compare its replies with the recorded ones
+pub fn replay(
+    entries: &[TraceEntry],
+    create: impl FnOnce(
+        Receiver<OrchestratorToPlanet>,
+        Sender<PlanetToOrchestrator>,
+        Receiver<ExplorerToPlanet>,
+    ) -> Planet,
+) -> ReplayReport {
+    let (tx_orch, rx_planet) = unbounded();
+    let (tx_planet, rx_orch) = unbounded();
+    let (tx_explorer, rx_explorer) = unbounded();
+    let mut planet = create(rx_planet, tx_planet, rx_explorer);
+    let handle = thread::spawn(move || planet.run());
+
+    let mut explorers: HashMap<u32, Receiver<PlanetToExplorer>> = HashMap::new();
+    let workshop = Workshop::new();
+    let mut report = ReplayReport::default();
+    let mut skipping = false;
+
+    for (index, entry) in entries.iter().enumerate() {
+        if entry.is_inbound() {
+            let msg = entry
+                .replay
+                .as_ref()
+                .and_then(|replayable| match replayable {
+                    Replayable::IncomingExplorer { explorer_id } => {
+                        let (tx, rx) = unbounded();
+                        explorers.insert(*explorer_id, rx);
+                        Some(Inbound::Orchestrator(
+                            OrchestratorToPlanet::IncomingExplorerRequest {
+                                explorer_id: *explorer_id,
+                                new_sender: tx,
+                            },
+                        ))
+                    }
+                    other => rebuild(other, &workshop),
+                });
+            skipping = msg.is_none();
+            match msg {
+                Some(Inbound::Orchestrator(msg)) => {
+                    let _ = tx_orch.send(msg);
+                    report.replayed += 1;
+                }
+                Some(Inbound::Explorer(msg)) => {
+                    let _ = tx_explorer.send(msg);
+                    report.replayed += 1;
+                }
+                None => report.skipped += 1,
+            }
+            continue;
+        }
+        if skipping {
+            continue;
+        }
+
+        let actual = match entry.direction {
+            Direction::ToOrchestrator => rx_orch
+                .recv_timeout(REPLAY_TIMEOUT)
+                .ok()
+                .map(|m| format!("{m:?}")),
+            _ => entry
+                .explorer_id
+                .and_then(|id| explorers.get(&id))
+                .and_then(|rx| rx.recv_timeout(REPLAY_TIMEOUT).ok())
+                .map(|m| format!("{m:?}")),
+        };
+        if actual.as_deref() != Some(entry.message.as_str()) {
+            report.diffs.push(TraceDiff {
+                entry: index,
+                expected: entry.message.clone(),
+                actual,
+            });
+        }
+    }
+
+    // a planet that wasn't killed by the trace stops when its orchestrator disconnects
+    drop(tx_orch);
+    let _ = handle.join();
+    report
+}
+
+enum Inbound {
+    Orchestrator(OrchestratorToPlanet),
+    Explorer(ExplorerToPlanet),
+}
+
+fn rebuild(replayable: &Replayable, workshop: &Workshop) -> Option<Inbound> {
+    use Inbound::{Explorer, Orchestrator};
+
+    Some(match replayable {
+        Replayable::StartPlanetAI => Orchestrator(OrchestratorToPlanet::StartPlanetAI),
+        Replayable::StopPlanetAI => Orchestrator(OrchestratorToPlanet::StopPlanetAI),
+        Replayable::KillPlanet => Orchestrator(OrchestratorToPlanet::KillPlanet),
+        Replayable::Sunray => Orchestrator(OrchestratorToPlanet::Sunray(Sunray::default())),
+        Replayable::Asteroid => Orchestrator(OrchestratorToPlanet::Asteroid(Asteroid::default())),
+        Replayable::InternalStateRequest => {
+            Orchestrator(OrchestratorToPlanet::InternalStateRequest)
+        }
+        // the sender is created by the replayer
+        Replayable::IncomingExplorer { .. } => return None,
+        Replayable::OutgoingExplorer { explorer_id } => {
+            Orchestrator(OrchestratorToPlanet::OutgoingExplorerRequest {
+                explorer_id: *explorer_id,
+            })
+        }
+        Replayable::SupportedResources { explorer_id } => {
+            Explorer(ExplorerToPlanet::SupportedResourceRequest {
+                explorer_id: *explorer_id,
+            })
+        }
+        Replayable::SupportedCombinations { explorer_id } => {
+            Explorer(ExplorerToPlanet::SupportedCombinationRequest {
+                explorer_id: *explorer_id,
+            })
+        }
+        Replayable::Generate {
+            explorer_id,
+            resource,
+        } => Explorer(ExplorerToPlanet::GenerateResourceRequest {
+            explorer_id: *explorer_id,
+            resource: BasicResourceType::from_name(resource)?,
+        }),
+        Replayable::AvailableEnergyCell { explorer_id } => {
+            Explorer(ExplorerToPlanet::AvailableEnergyCellRequest {
+                explorer_id: *explorer_id,
+            })
+        }
+        Replayable::Combine {
+            explorer_id,
+            resource,
+            inputs,
+        } => {
+            let resource = ComplexResourceType::from_name(resource)?;
+            let lhs = workshop.make(resource_from_name(&inputs[0])?)?;
+            let rhs = workshop.make(resource_from_name(&inputs[1])?)?;
+            Explorer(ExplorerToPlanet::CombineResourceRequest {
+                explorer_id: *explorer_id,
+                msg: combination_request(resource, lhs, rhs).ok()?,
+            })
+        }
+    })
+}
+
+/// Planets that are never run, lending their generator and combinator to the replayer
+struct Workshop {
+    generation: Planet,
+    combination: Planet,
+}
+
+impl Workshop {
+    fn new() -> Self {
+        Self {
+            generation: Self::planet(PlanetType::D, BasicResourceType::ALL, &[]),
+            combination: Self::planet(
+                PlanetType::C,
+                &[BasicResourceType::Carbon],
+                ComplexResourceType::ALL,
+            ),
+        }
+    }
+
+    fn planet(
+        planet_type: PlanetType,
+        gen_rules: &[BasicResourceType],
+        comb_rules: &[ComplexResourceType],
+    ) -> Planet {
+        let (_tx_orch, rx_orch) = unbounded();
+        let (tx_planet, _rx_planet) = unbounded();
+        let (_tx_explorer, rx_explorer) = unbounded();
+        let ai = AI::new(
+            Participant::new(ActorType::Planet, 0u32),
+            Box::new(Defensive),
+        );
+        Planet::new(
+            0,
+            planet_type,
+            Box::new(ai),
+            gen_rules.to_vec(),
+            comb_rules.to_vec(),
+            (rx_orch, tx_planet),
+            rx_explorer,
+        )
+        .expect("the workshop rules fit the planet types")
+    }
+
+    /// Make a resource from scratch, each step with a freshly charged cell
+    fn make(&self, resource: ResourceType) -> Option<GenericResource> {
+        let mut cell = EnergyCell::new();
+        cell.charge(Sunray::default());
+        match resource {
+            ResourceType::Basic(basic) => self
+                .generation
+                .generator()
+                .try_make(basic, &mut cell)
+                .ok()
+                .map(GenericResource::BasicResources),
+            ResourceType::Complex(complex) => {
+                let (lhs, rhs) = recipe_inputs(complex);
+                let msg = combination_request(complex, self.make(lhs)?, self.make(rhs)?).ok()?;
+                self.combination
+                    .combinator()
+                    .try_make(msg, &mut cell)
+                    .ok()
+                    .map(GenericResource::ComplexResources)
+            }
+        }
+    }
+}
@@ -0,0 +1,210 @@
+use common_game::components::planet::PlanetType;
+use common_game::components::resource::{BasicResourceType, ComplexResourceType};
+use the_compiler_strikes_back::DefenseReserve;
+use the_compiler_strikes_back::allocation::{CellPolicy, CellStatistics, CellUsage};
+use the_compiler_strikes_back::events::{EventLog, PlanetEvent};
+use the_compiler_strikes_back::harness::PlanetHarness;
+use the_compiler_strikes_back::planet::PlanetBuilder;
+use the_compiler_strikes_back::strategy::{Defensive, Economic};
+
+fn spawn(policy: CellPolicy, statistics: &CellStatistics) -> PlanetHarness {
+    PlanetHarness::spawn_with(
+        1,
+        PlanetBuilder::new(1)
+            .planet_type(PlanetType::A)
+            .comb_rules(vec![])
+            .strategy(Box::new(Economic))
+            .defense_reserve(DefenseReserve::Unreserved)
+            .cell_policy(policy)
+            .cell_statistics(statistics.clone()),
+    )
+    .unwrap()
+}
+
+fn explorer_use(explorers: u64) -> CellUsage {
+    CellUsage {
+        rockets: 0,
+        explorers,
+    }
+}
+
+//test for the round robin policy spreading the explorer requests over the cells
+#[test]
+fn test_round_robin_spreads_cells() {
+    let statistics = CellStatistics::new();
+    let harness = spawn(CellPolicy::RoundRobin, &statistics);
+    let explorer = harness.attach_explorer(101).unwrap();
+
+    harness.send_sunray().unwrap();
+    harness.send_sunray().unwrap();
+    assert!(
+        explorer
+            .generate(BasicResourceType::Silicon)
+            .unwrap()
+            .is_some()
+    );
+    // the sunray recharges cell 0, but the next request moves on to cell 1
+    harness.send_sunray().unwrap();
+    assert!(
+        explorer
+            .generate(BasicResourceType::Silicon)
+            .unwrap()
+            .is_some()
+    );
+    assert!(
+        explorer
+            .generate(BasicResourceType::Silicon)
+            .unwrap()
+            .is_some()
+    );
+    assert_eq!(statistics.usage(), vec![explorer_use(2), explorer_use(1)]);
+}
+
+//test for the first fit policy always spending the lowest charged cell
+#[test]
+fn test_first_fit_reuses_first_cell() {
+    let statistics = CellStatistics::new();
+    let harness = spawn(CellPolicy::FirstFit, &statistics);
+    let explorer = harness.attach_explorer(101).unwrap();
+
+    harness.send_sunray().unwrap();
+    harness.send_sunray().unwrap();
+    assert!(
+        explorer
+            .generate(BasicResourceType::Silicon)
+            .unwrap()
+            .is_some()
+    );
+    harness.send_sunray().unwrap();
+    assert!(
+        explorer
+            .generate(BasicResourceType::Silicon)
+            .unwrap()
+            .is_some()
+    );
+    assert_eq!(statistics.usage(), vec![explorer_use(2)]);
+    assert_eq!(statistics.cell(1), explorer_use(0));
+}
+
+//test for rockets built from the last charged cell, leaving the first ones to explorers
+#[test]
+fn test_keep_last_for_rocket() {
+    let statistics = CellStatistics::new();
+    let harness = spawn(CellPolicy::KeepLastForRocket, &statistics);
+    let explorer = harness.attach_explorer(101).unwrap();
+
+    for _ in 0..3 {
+        harness.send_sunray().unwrap();
+    }
+    assert!(harness.send_asteroid().unwrap().is_some());
+    assert!(
+        explorer
+            .generate(BasicResourceType::Silicon)
+            .unwrap()
+            .is_some()
+    );
+    assert_eq!(
+        statistics.usage(),
+        vec![
+            explorer_use(1),
+            explorer_use(0),
+            CellUsage {
+                rockets: 1,
+                explorers: 0,
+            },
+        ]
+    );
+}
+
+//test for the last charged cell kept from explorers for the rocket
+#[test]
+fn test_keep_last_refused_to_explorers() {
+    let statistics = CellStatistics::new();
+    let harness = spawn(CellPolicy::KeepLastForRocket, &statistics);
+    let explorer = harness.attach_explorer(101).unwrap();
+
+    harness.send_sunray().unwrap();
+    harness.send_sunray().unwrap();
+    assert!(
+        explorer
+            .generate(BasicResourceType::Silicon)
+            .unwrap()
+            .is_some()
+    );
+    // the last cell isn't offered either
+    assert_eq!(explorer.available_cells().unwrap(), 0);
+    assert!(
+        explorer
+            .generate(BasicResourceType::Silicon)
+            .unwrap()
+            .is_none()
+    );
+    assert!(harness.send_asteroid().unwrap().is_some());
+    assert_eq!(
+        statistics.usage(),
+        vec![
+            explorer_use(1),
+            CellUsage {
+                rockets: 1,
+                explorers: 0,
+            },
+        ]
+    );
+}
+
+//test for the last charged cell kept only while a rocket can use it, refused with its own reason
+#[test]
+fn test_keep_last_only_for_missing_rocket() {
+    let keep_last = |builder: PlanetBuilder| {
+        PlanetHarness::spawn_with(1, builder.cell_policy(CellPolicy::KeepLastForRocket)).unwrap()
+    };
+
+    // type B planets can't have a rocket
+    let harness = keep_last(PlanetBuilder::of_type(
+        1,
+        PlanetType::B,
+        vec![BasicResourceType::Hydrogen, BasicResourceType::Oxygen],
+        vec![ComplexResourceType::Water],
+    ));
+    let explorer = harness.attach_explorer(101).unwrap();
+    harness.send_sunray().unwrap();
+    assert_eq!(explorer.available_cells().unwrap(), 1);
+    assert!(
+        explorer
+            .generate(BasicResourceType::Oxygen)
+            .unwrap()
+            .is_some()
+    );
+
+    // the defensive strategy already built the rocket with the first sunray
+    let harness = keep_last(PlanetBuilder::new(1).strategy(Box::new(Defensive)));
+    let explorer = harness.attach_explorer(101).unwrap();
+    harness.send_sunray().unwrap();
+    harness.send_sunray().unwrap();
+    assert!(harness.request_state().unwrap().has_rocket);
+    assert!(
+        explorer
+            .generate(BasicResourceType::Silicon)
+            .unwrap()
+            .is_some()
+    );
+
+    let event_log = EventLog::new(16);
+    let harness = keep_last(
+        PlanetBuilder::new(1)
+            .strategy(Box::new(Economic))
+            .event_log(event_log.clone()),
+    );
+    let explorer = harness.attach_explorer(101).unwrap();
+    harness.send_sunray().unwrap();
+    assert!(
+        explorer
+            .generate(BasicResourceType::Silicon)
+            .unwrap()
+            .is_none()
+    );
+    assert!(event_log.events().iter().any(|e| matches!(
+        &e.event,
+        PlanetEvent::RequestDenied { reason, .. } if reason == "The last charged cell is kept for the rocket"
+    )));
+}
@@ -0,0 +1,166 @@
+use common_game::components::planet::PlanetType;
+use common_game::components::resource::{
+    BasicResourceType, ComplexResourceRequest, ComplexResourceType,
+};
+use the_compiler_strikes_back::economy::{Account, EconomyConfig, PriceList};
+use the_compiler_strikes_back::events::{EventLog, PlanetEvent};
+use the_compiler_strikes_back::harness::PlanetHarness;
+use the_compiler_strikes_back::planet::PlanetBuilder;
+use the_compiler_strikes_back::strategy::Economic;
+
+//test for the credit spent on generations and earned by the inputs of a combination
+#[test]
+fn test_economy_ledger() {
+    let event_log = EventLog::new(64);
+    let prices = PriceList::new(1).complex(ComplexResourceType::Water, 3);
+    let harness = PlanetHarness::spawn_with(
+        1,
+        PlanetBuilder::of_type(
+            1,
+            PlanetType::B,
+            vec![BasicResourceType::Hydrogen, BasicResourceType::Oxygen],
+            vec![ComplexResourceType::Water],
+        )
+        .strategy(Box::new(Economic))
+        .economy(EconomyConfig {
+            prices,
+            welcome_credit: 2,
+        })
+        .event_log(event_log.clone()),
+    )
+    .unwrap();
+    let explorer = harness.attach_explorer(101).unwrap();
+
+    harness.send_sunray().unwrap();
+    let hydrogen = explorer
+        .generate(BasicResourceType::Hydrogen)
+        .unwrap()
+        .unwrap();
+    harness.send_sunray().unwrap();
+    let oxygen = explorer
+        .generate(BasicResourceType::Oxygen)
+        .unwrap()
+        .unwrap();
+    // the welcome credit is spent
+    harness.send_sunray().unwrap();
+    assert!(
+        explorer
+            .generate(BasicResourceType::Oxygen)
+            .unwrap()
+            .is_none()
+    );
+
+    // the inputs are worth 2, the water costs 3: one credit is missing
+    let msg =
+        ComplexResourceRequest::Water(hydrogen.to_hydrogen().unwrap(), oxygen.to_oxygen().unwrap());
+    let msg = match explorer.combine(msg).unwrap() {
+        Err((reason, r1, r2)) => {
+            assert_eq!(reason, "The explorer doesn't have enough credit");
+            ComplexResourceRequest::Water(r1.to_hydrogen().unwrap(), r2.to_oxygen().unwrap())
+        }
+        Ok(complex) => panic!("Unexpected combination {complex:?}"),
+    };
+    harness.request_state().unwrap();
+
+    // a new explorer with its own welcome credit can afford it
+    let other = harness.attach_explorer(102).unwrap();
+    harness.send_sunray().unwrap();
+    assert!(other.combine(msg).unwrap().is_ok());
+    harness.request_state().unwrap();
+
+    let ledgers: Vec<Vec<(u32, Account)>> = event_log
+        .events()
+        .into_iter()
+        .filter_map(|e| match e.event {
+            PlanetEvent::LedgerReported { accounts } => Some(accounts),
+            _ => None,
+        })
+        .collect();
+    let spent = Account {
+        balance: 0,
+        earned: 0,
+        spent: 2,
+    };
+    assert_eq!(ledgers[0], vec![(101, spent)]);
+    assert_eq!(
+        ledgers[1],
+        vec![
+            (101, spent),
+            (
+                102,
+                Account {
+                    balance: 1,
+                    earned: 2,
+                    spent: 3,
+                }
+            )
+        ]
+    );
+}
+
+//test for huge prices: the credit saturates instead of overflowing
+#[test]
+fn test_economy_saturates() {
+    let event_log = EventLog::new(64);
+    let prices = PriceList::new(1)
+        .basic(BasicResourceType::Hydrogen, u32::MAX)
+        .basic(BasicResourceType::Oxygen, u32::MAX);
+    let harness = PlanetHarness::spawn_with(
+        1,
+        PlanetBuilder::of_type(
+            1,
+            PlanetType::B,
+            vec![BasicResourceType::Hydrogen, BasicResourceType::Oxygen],
+            vec![ComplexResourceType::Water],
+        )
+        .strategy(Box::new(Economic))
+        .economy(EconomyConfig {
+            prices,
+            welcome_credit: u32::MAX,
+        })
+        .event_log(event_log.clone()),
+    )
+    .unwrap();
+
+    // each explorer spends its whole welcome credit on a single resource
+    harness.send_sunray().unwrap();
+    let hydrogen = harness
+        .attach_explorer(101)
+        .unwrap()
+        .generate(BasicResourceType::Hydrogen)
+        .unwrap()
+        .unwrap();
+    harness.send_sunray().unwrap();
+    let oxygen = harness
+        .attach_explorer(102)
+        .unwrap()
+        .generate(BasicResourceType::Oxygen)
+        .unwrap()
+        .unwrap();
+
+    // the inputs are worth twice u32::MAX
+    let combiner = harness.attach_explorer(103).unwrap();
+    harness.send_sunray().unwrap();
+    let msg =
+        ComplexResourceRequest::Water(hydrogen.to_hydrogen().unwrap(), oxygen.to_oxygen().unwrap());
+    assert!(combiner.combine(msg).unwrap().is_ok());
+    harness.request_state().unwrap();
+
+    let ledger = event_log
+        .events()
+        .into_iter()
+        .rev()
+        .find_map(|e| match e.event {
+            PlanetEvent::LedgerReported { accounts } => Some(accounts),
+            _ => None,
+        })
+        .unwrap();
+    assert!(ledger.contains(&(
+        103,
+        Account {
+            balance: u32::MAX - 1,
+            earned: u32::MAX,
+            spent: 1,
+        }
+    )));
+}
@@ -0,0 +1,67 @@
+use common_game::components::planet::{DummyPlanetState, PlanetType};
+use the_compiler_strikes_back::harness::PlanetHarness;
+use the_compiler_strikes_back::planet::PlanetBuilder;
+use the_compiler_strikes_back::strategy::Economic;
+use the_compiler_strikes_back::{DefenseReserve, EnergyBudget};
+
+//test for the cell kept for the next rocket of a planet without rocket, by default
+#[test]
+fn test_energy_next_rocket() {
+    let reserved =
+        PlanetHarness::spawn_with(1, PlanetBuilder::new(1).strategy(Box::new(Economic))).unwrap();
+    let explorer = reserved.attach_explorer(101).unwrap();
+    reserved.send_sunray().unwrap();
+    assert_eq!(explorer.available_cells().unwrap(), 0);
+
+    // without a reserve every charged cell is reported
+    let unreserved = PlanetHarness::spawn_with(
+        2,
+        PlanetBuilder::new(2)
+            .strategy(Box::new(Economic))
+            .defense_reserve(DefenseReserve::Unreserved),
+    )
+    .unwrap();
+    let explorer = unreserved.attach_explorer(101).unwrap();
+    unreserved.send_sunray().unwrap();
+    assert_eq!(explorer.available_cells().unwrap(), 1);
+
+    // a planet that can't have rockets keeps nothing for them
+    let no_rocket = PlanetHarness::spawn_with(
+        3,
+        PlanetBuilder::new(3)
+            .planet_type(PlanetType::D)
+            .comb_rules(vec![])
+            .strategy(Box::new(Economic))
+            .defense_reserve(DefenseReserve::NextRocket),
+    )
+    .unwrap();
+    let explorer = no_rocket.attach_explorer(101).unwrap();
+    no_rocket.send_sunray().unwrap();
+    no_rocket.send_sunray().unwrap();
+    assert_eq!(explorer.available_cells().unwrap(), 2);
+}
+
+//test for the split of the charged cells
+#[test]
+fn test_energy_budget() {
+    let state = DummyPlanetState {
+        energy_cells: vec![true, true, false, true, false],
+        charged_cells_count: 3,
+        has_rocket: true,
+    };
+    let budget = EnergyBudget::new(&state, true, DefenseReserve::NextRocket, 0.0);
+    assert_eq!(budget.reserved, 0);
+    assert_eq!(budget.available(), 3);
+
+    let budget = EnergyBudget::new(&state, true, DefenseReserve::Cells(2), 0.0);
+    assert_eq!(budget.available(), 1);
+
+    let budget = EnergyBudget::new(&state, true, DefenseReserve::Cells(5), 0.0);
+    assert_eq!(budget.reserved, 3);
+    assert_eq!(budget.available(), 0);
+
+    let budget = EnergyBudget::new(&state, true, DefenseReserve::Adaptive, 0.4);
+    assert_eq!(budget.reserved, 2);
+    let budget = EnergyBudget::new(&state, false, DefenseReserve::Adaptive, 0.4);
+    assert_eq!(budget.reserved, 0);
+}
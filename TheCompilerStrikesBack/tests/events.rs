@@ -0,0 +1,99 @@
+use common_game::components::planet::PlanetType;
+use common_game::components::resource::BasicResourceType;
+use common_game::protocols::planet_explorer::{ExplorerToPlanet, ExplorerToPlanetKind};
+use the_compiler_strikes_back::events::{EventLog, PlanetEvent};
+use the_compiler_strikes_back::harness::PlanetHarness;
+use the_compiler_strikes_back::planet::PlanetBuilder;
+use the_compiler_strikes_back::strategy::Defensive;
+
+//test for the events captured while the planet defends itself and serves an explorer
+#[test]
+fn test_event_log_decisions() {
+    let event_log = EventLog::new(64);
+    let harness = PlanetHarness::spawn_with(
+        1,
+        PlanetBuilder::new(1)
+            .strategy(Box::new(Defensive))
+            .event_log(event_log.clone()),
+    )
+    .unwrap();
+    let explorer = harness.attach_explorer(101).unwrap();
+
+    harness.send_sunray().unwrap(); // charged, then used for the rocket
+    harness.send_sunray().unwrap(); // charged
+    harness.send_sunray().unwrap(); // wasted
+    assert!(harness.send_asteroid().unwrap().is_some());
+
+    explorer
+        .request(ExplorerToPlanet::GenerateResourceRequest {
+            explorer_id: 101,
+            resource: BasicResourceType::Oxygen,
+        })
+        .unwrap();
+
+    let events: Vec<PlanetEvent> = event_log.events().into_iter().map(|e| e.event).collect();
+    assert_eq!(
+        events,
+        vec![
+            PlanetEvent::ExplorerArrived { explorer_id: 101 },
+            PlanetEvent::CellCharged,
+            PlanetEvent::RocketBuilt,
+            PlanetEvent::CellCharged,
+            PlanetEvent::CellWasted { total_wasted: 1 },
+            PlanetEvent::RocketLaunched,
+            PlanetEvent::RequestDenied {
+                explorer_id: 101,
+                request: ExplorerToPlanetKind::GenerateResourceRequest,
+                reason: "there isn't a recipe for Oxygen".to_string(),
+            },
+        ]
+    );
+    assert_eq!(event_log.for_explorer(101).len(), 2);
+    assert!(event_log.events().iter().all(|e| e.planet_id == 1));
+}
+
+//test for the rocket builds of a planet type that can't have rockets:
+//only the asteroid tries one, the sunrays don't
+#[test]
+fn test_event_log_rocket_build_failed() {
+    let event_log = EventLog::new(64);
+    let harness = PlanetHarness::spawn_with(
+        1,
+        PlanetBuilder::new(1)
+            .planet_type(PlanetType::D)
+            .comb_rules(vec![])
+            .strategy(Box::new(Defensive))
+            .event_log(event_log.clone()),
+    )
+    .unwrap();
+
+    harness.send_sunray().unwrap();
+    harness.send_sunray().unwrap();
+    assert!(harness.send_asteroid().unwrap().is_none());
+
+    let events: Vec<PlanetEvent> = event_log.events().into_iter().map(|e| e.event).collect();
+    assert_eq!(events.len(), 3);
+    assert_eq!(
+        events[..2],
+        [PlanetEvent::CellCharged, PlanetEvent::CellCharged]
+    );
+    assert!(matches!(events[2], PlanetEvent::RocketBuildFailed { .. }));
+    assert!(!events.contains(&PlanetEvent::RocketBuilt));
+}
+
+//test for the ring buffer: only the last events are kept
+#[test]
+fn test_event_log_capacity() {
+    let event_log = EventLog::new(2);
+    event_log.record(1, PlanetEvent::CellCharged);
+    event_log.record(1, PlanetEvent::RocketBuilt);
+    event_log.record(1, PlanetEvent::RocketLaunched);
+
+    let events: Vec<PlanetEvent> = event_log.events().into_iter().map(|e| e.event).collect();
+    assert_eq!(
+        events,
+        vec![PlanetEvent::RocketBuilt, PlanetEvent::RocketLaunched]
+    );
+    event_log.clear();
+    assert!(event_log.is_empty());
+}
@@ -0,0 +1,60 @@
+use the_compiler_strikes_back::error::PlanetCreationError;
+use the_compiler_strikes_back::galaxy::{GalaxyConfig, simulate};
+use the_compiler_strikes_back::harness::HarnessError;
+
+//test for the simulator determinism: the same seed gives the same report
+#[test]
+fn test_galaxy_deterministic() {
+    let config = GalaxyConfig {
+        seed: 42,
+        ticks: 200,
+        planets: 3,
+        strategy: "balanced".to_string(),
+        ..GalaxyConfig::default()
+    };
+
+    let first = simulate(&config).unwrap();
+    let second = simulate(&config).unwrap();
+    assert_eq!(first, second);
+    assert_eq!(first.planets.len(), 3);
+
+    for planet in &first.planets {
+        // every rocket and every cell given to explorers needs a sunray
+        assert!(planet.rockets_built + planet.cells_to_explorers <= planet.sunrays);
+        assert!(planet.cells_to_explorers <= planet.explorer_requests);
+    }
+}
+
+//test for the strategies: without sunrays no strategy can defend the planet
+#[test]
+fn test_galaxy_no_sunrays() {
+    for strategy in ["defensive", "economic", "balanced"] {
+        let config = GalaxyConfig {
+            seed: 7,
+            ticks: 50,
+            planets: 2,
+            strategy: strategy.to_string(),
+            sunray_probability: 0.0,
+            asteroid_probability: 1.0,
+            ..GalaxyConfig::default()
+        };
+
+        let report = simulate(&config).unwrap();
+        assert_eq!(report.survival_rate(), 0.0);
+        assert!(report.planets.iter().all(|p| p.destroyed_at == Some(0)));
+    }
+}
+
+//test for the configuration: an unknown strategy is reported instead of panicking
+#[test]
+fn test_galaxy_unknown_strategy() {
+    let config = GalaxyConfig {
+        strategy: "reckless".to_string(),
+        ..GalaxyConfig::default()
+    };
+
+    assert!(matches!(
+        simulate(&config),
+        Err(HarnessError::Creation(PlanetCreationError::Other(_)))
+    ));
+}
@@ -0,0 +1,68 @@
+use common_game::components::resource::BasicResourceType;
+use common_game::protocols::planet_explorer::{ExplorerToPlanet, PlanetToExplorer};
+use the_compiler_strikes_back::PlanetCreationError;
+use the_compiler_strikes_back::harness::{HarnessError, PlanetHarness};
+use the_compiler_strikes_back::planet::PlanetBuilder;
+use the_compiler_strikes_back::strategy::{Defensive, Economic};
+
+//test for the harness orchestrator helpers
+#[test]
+fn test_harness_orchestrator() {
+    let harness = PlanetHarness::spawn(1, Box::new(Defensive)).unwrap();
+
+    harness.send_sunray().unwrap();
+    let planet_state = harness.request_state().unwrap();
+    assert!(planet_state.has_rocket);
+    assert_eq!(planet_state.charged_cells_count, 0);
+
+    assert!(harness.send_asteroid().unwrap().is_some());
+    assert!(harness.send_asteroid().unwrap().is_none());
+
+    assert!(harness.kill().unwrap().is_ok());
+}
+
+//test for an explorer attached through the harness
+#[test]
+fn test_harness_explorer() {
+    let harness =
+        PlanetHarness::spawn_with(1, PlanetBuilder::new(1).strategy(Box::new(Economic))).unwrap();
+    let explorer = harness.attach_explorer(101).unwrap();
+
+    harness.send_sunray().unwrap();
+    match explorer
+        .request(ExplorerToPlanet::GenerateResourceRequest {
+            explorer_id: explorer.explorer_id(),
+            resource: BasicResourceType::Silicon,
+        })
+        .unwrap()
+    {
+        PlanetToExplorer::GenerateResourceResponse { resource } => {
+            assert_eq!(resource.unwrap().get_type(), BasicResourceType::Silicon)
+        }
+        other => panic!("Unattended message {other:?}"),
+    }
+
+    harness.stop().unwrap();
+    assert!(matches!(
+        explorer
+            .request(ExplorerToPlanet::AvailableEnergyCellRequest { explorer_id: 101 })
+            .unwrap(),
+        PlanetToExplorer::Stopped
+    ));
+    harness.start().unwrap();
+
+    harness.detach_explorer(101).unwrap();
+    // the planet is killed when the harness is dropped
+}
+
+//test for a planet configuration rejected by the builder
+#[test]
+fn test_harness_creation_error() {
+    let builder = PlanetBuilder::new(1).gen_rules(Vec::new());
+    match PlanetHarness::spawn_with(1, builder) {
+        Err(HarnessError::Creation(err)) => {
+            assert!(matches!(err, PlanetCreationError::EmptyGenerationRules(_)))
+        }
+        _ => panic!("Expected a creation error"),
+    }
+}
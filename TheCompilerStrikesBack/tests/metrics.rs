@@ -0,0 +1,96 @@
+use common_game::components::resource::BasicResourceType;
+use common_game::protocols::planet_explorer::ExplorerToPlanet;
+use the_compiler_strikes_back::harness::PlanetHarness;
+use the_compiler_strikes_back::metrics::PlanetMetrics;
+use the_compiler_strikes_back::planet::PlanetBuilder;
+use the_compiler_strikes_back::strategy::{Defensive, Economic};
+
+//test for the counters updated while the planet defends itself and serves an explorer
+#[test]
+fn test_metrics_counters() {
+    let metrics = PlanetMetrics::new();
+    let harness = PlanetHarness::spawn_with(
+        1,
+        PlanetBuilder::new(1)
+            .strategy(Box::new(Defensive))
+            .metrics(metrics.clone()),
+    )
+    .unwrap();
+    let explorer = harness.attach_explorer(101).unwrap();
+
+    harness.send_sunray().unwrap(); // charged, then used for the rocket
+    harness.send_sunray().unwrap(); // charged
+    harness.send_sunray().unwrap(); // wasted
+    assert!(harness.send_asteroid().unwrap().is_some());
+    assert!(harness.send_asteroid().unwrap().is_some()); // rocket built from the last cell
+    assert!(harness.send_asteroid().unwrap().is_none());
+
+    for resource in [BasicResourceType::Silicon, BasicResourceType::Oxygen] {
+        explorer
+            .request(ExplorerToPlanet::GenerateResourceRequest {
+                explorer_id: 101,
+                resource,
+            })
+            .unwrap();
+    }
+
+    let counters = metrics.counters(1).unwrap();
+    assert_eq!(counters.sunrays_received, 3);
+    assert_eq!(counters.cells_charged, 2);
+    assert_eq!(counters.sunrays_wasted, 1);
+    assert_eq!(counters.rockets_built, 2);
+    assert_eq!(counters.asteroids_survived, 2);
+    assert_eq!(counters.asteroids_failed, 1);
+    assert_eq!(
+        counters.explorer_requests("GenerateResourceRequest", "denied"),
+        2
+    );
+    assert_eq!(
+        counters.explorer_requests("GenerateResourceRequest", "served"),
+        0
+    );
+}
+
+//test for the Prometheus text rendering of several planets sharing a registry
+#[test]
+fn test_metrics_render() {
+    let metrics = PlanetMetrics::new();
+    let first = PlanetHarness::spawn_with(
+        1,
+        PlanetBuilder::new(1)
+            .strategy(Box::new(Economic))
+            .metrics(metrics.clone()),
+    )
+    .unwrap();
+    let second = PlanetHarness::spawn_with(
+        2,
+        PlanetBuilder::new(2)
+            .strategy(Box::new(Economic))
+            .metrics(metrics.clone()),
+    )
+    .unwrap();
+    let explorer = first.attach_explorer(101).unwrap();
+
+    first.send_sunray().unwrap();
+    second.send_sunray().unwrap();
+    second.send_sunray().unwrap();
+    explorer
+        .request(ExplorerToPlanet::GenerateResourceRequest {
+            explorer_id: 101,
+            resource: BasicResourceType::Silicon,
+        })
+        .unwrap();
+
+    let text = metrics.render();
+    assert!(text.contains("# TYPE planet_sunrays_received_total counter"));
+    assert!(text.contains("planet_sunrays_received_total{planet=\"1\"} 1"));
+    assert!(text.contains("planet_sunrays_received_total{planet=\"2\"} 2"));
+    assert!(text.contains(
+        "planet_explorer_requests_total{planet=\"1\",request=\"GenerateResourceRequest\",outcome=\"served\"} 1"
+    ));
+
+    let path = std::env::temp_dir().join("the_compiler_strikes_back_metrics.prom");
+    metrics.write_to(&path).unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), metrics.render());
+    let _ = std::fs::remove_file(path);
+}
@@ -0,0 +1,113 @@
+use common_game::components::planet::PlanetType;
+use common_game::components::resource::{
+    BasicResourceType, ComplexResourceRequest, ComplexResourceType, ResourceType,
+};
+use the_compiler_strikes_back::harness::PlanetHarness;
+
+const CARBON: ResourceType = ResourceType::Basic(BasicResourceType::Carbon);
+
+//test for the resources generated by planets of type A and D
+#[test]
+fn test_planet_types_generation() {
+    let harness =
+        PlanetHarness::spawn_of_type(1, PlanetType::A, vec![BasicResourceType::Oxygen], vec![])
+            .unwrap();
+    let explorer = harness.attach_explorer(101).unwrap();
+    harness.send_sunray().unwrap(); // used for the rocket
+    harness.send_sunray().unwrap();
+    let oxygen = explorer
+        .generate(BasicResourceType::Oxygen)
+        .unwrap()
+        .unwrap();
+    assert_eq!(oxygen.get_type(), BasicResourceType::Oxygen);
+    harness.send_sunray().unwrap();
+    assert!(
+        explorer
+            .generate(BasicResourceType::Carbon)
+            .unwrap()
+            .is_none()
+    );
+
+    let harness = PlanetHarness::spawn_of_type(
+        1,
+        PlanetType::D,
+        vec![BasicResourceType::Carbon, BasicResourceType::Hydrogen],
+        vec![],
+    )
+    .unwrap();
+    let explorer = harness.attach_explorer(101).unwrap();
+    for resource in [BasicResourceType::Carbon, BasicResourceType::Hydrogen] {
+        harness.send_sunray().unwrap();
+        let generated = explorer.generate(resource).unwrap().unwrap();
+        assert_eq!(generated.get_type(), resource);
+    }
+}
+
+//test for a type B planet combining the resources it generates
+#[test]
+fn test_planet_types_combination() {
+    let harness = PlanetHarness::spawn_of_type(
+        1,
+        PlanetType::B,
+        vec![BasicResourceType::Hydrogen, BasicResourceType::Oxygen],
+        vec![ComplexResourceType::Water],
+    )
+    .unwrap();
+    let explorer = harness.attach_explorer(101).unwrap();
+    harness.send_sunray().unwrap();
+    let hydrogen = explorer.generate(BasicResourceType::Hydrogen).unwrap();
+    harness.send_sunray().unwrap();
+    let oxygen = explorer.generate(BasicResourceType::Oxygen).unwrap();
+
+    harness.send_sunray().unwrap();
+    let water = explorer
+        .combine(ComplexResourceRequest::Water(
+            hydrogen.unwrap().to_hydrogen().unwrap(),
+            oxygen.unwrap().to_oxygen().unwrap(),
+        ))
+        .unwrap();
+    assert_eq!(water.unwrap().get_type(), ComplexResourceType::Water);
+}
+
+//test for a combination rule enabled at creation: it is served without a dedicated handler
+#[test]
+fn test_recipe_enabled_by_rules() {
+    for (comb_rules, enabled) in [
+        (vec![ComplexResourceType::Diamond], true),
+        (vec![ComplexResourceType::Water], false),
+    ] {
+        let harness = PlanetHarness::spawn_of_type(
+            1,
+            PlanetType::C,
+            vec![BasicResourceType::Carbon],
+            comb_rules,
+        )
+        .unwrap();
+        let explorer = harness.attach_explorer(101).unwrap();
+        harness.send_sunray().unwrap(); // used for the rocket
+        let mut carbon = Vec::new();
+        for _ in 0..2 {
+            harness.send_sunray().unwrap();
+            let generated = explorer.generate(BasicResourceType::Carbon).unwrap();
+            carbon.push(generated.unwrap().to_carbon().unwrap());
+        }
+
+        harness.send_sunray().unwrap();
+        let (carbon2, carbon1) = (carbon.pop().unwrap(), carbon.pop().unwrap());
+        match explorer
+            .combine(ComplexResourceRequest::Diamond(carbon1, carbon2))
+            .unwrap()
+        {
+            Ok(complex) => {
+                assert!(enabled);
+                assert_eq!(complex.get_type(), ComplexResourceType::Diamond);
+            }
+            // Diamond isn't enabled: the carbons are handed back
+            Err((reason, r1, r2)) => {
+                assert!(!enabled);
+                assert_eq!(reason, "there isn't a recipe for Diamond");
+                assert_eq!([r1.get_type(), r2.get_type()], [CARBON, CARBON]);
+            }
+        }
+    }
+}
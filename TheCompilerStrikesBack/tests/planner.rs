@@ -0,0 +1,64 @@
+use common_game::components::resource::BasicResourceType::{Carbon, Hydrogen, Oxygen, Silicon};
+use common_game::components::resource::ComplexResourceType::{
+    AIPartner, Diamond, Life, Robot, Water,
+};
+use common_game::components::resource::ResourceType::{Basic, Complex};
+use crossbeam_channel::bounded;
+use std::collections::HashSet;
+use the_compiler_strikes_back::planet::create_planet;
+use the_compiler_strikes_back::planner::{CombinationPlan, PlanNode, plan};
+
+//test for the plan of an AI partner on our planet
+#[test]
+fn test_planner_our_planet() {
+    let (_tx_orch, rx_planet) = bounded(10);
+    let (tx_planet, _rx_orch) = bounded(10);
+    let (_tx_explorer, rx_explorer) = bounded(10);
+    let planet = create_planet(rx_planet, tx_planet, rx_explorer, 1);
+
+    let plan = plan(AIPartner, planet.generator(), planet.combinator());
+    assert_eq!(
+        plan.tree,
+        PlanNode::Combine {
+            resource: AIPartner,
+            inputs: Box::new((
+                PlanNode::Combine {
+                    resource: Robot,
+                    inputs: Box::new((
+                        PlanNode::Generate(Silicon),
+                        PlanNode::Missing(Complex(Life))
+                    )),
+                },
+                PlanNode::Combine {
+                    resource: Diamond,
+                    inputs: Box::new((
+                        PlanNode::Missing(Basic(Carbon)),
+                        PlanNode::Missing(Basic(Carbon))
+                    )),
+                },
+            )),
+        }
+    );
+    assert_eq!(plan.cells_required(), 4);
+    assert_eq!(
+        plan.missing_inputs(),
+        vec![Complex(Life), Basic(Carbon), Basic(Carbon)]
+    );
+    assert!(!plan.is_self_sufficient());
+}
+
+//test for plans built from the recipe lists received by an explorer
+#[test]
+fn test_planner_recipe_lists() {
+    let resources = HashSet::from([Hydrogen, Oxygen, Carbon]);
+    let combinations = HashSet::from([Water, Life]);
+
+    let life = CombinationPlan::new(Life, &resources, &combinations);
+    assert!(life.is_self_sufficient());
+    assert_eq!(life.cells_required(), 5);
+
+    let robot = CombinationPlan::new(Robot, &resources, &combinations);
+    assert_eq!(robot.tree, PlanNode::Missing(Complex(Robot)));
+    assert_eq!(robot.cells_required(), 0);
+    assert_eq!(robot.missing_inputs(), vec![Complex(Robot)]);
+}
@@ -0,0 +1,335 @@
+use common_game::components::asteroid::Asteroid;
+use common_game::components::planet::{
+    DummyPlanetState, Planet, PlanetAI, PlanetState, PlanetType,
+};
+use common_game::components::resource::{
+    BasicResourceType, Combinator, ComplexResourceRequest, Generator, GenericResource, ResourceType,
+};
+use common_game::components::rocket::Rocket;
+use common_game::components::sunray::Sunray;
+use common_game::protocols::orchestrator_planet::{OrchestratorToPlanet, PlanetToOrchestrator};
+use common_game::protocols::planet_explorer::{ExplorerToPlanet, PlanetToExplorer};
+use proptest::prelude::*;
+use the_compiler_strikes_back::conservation::ConservationAudit;
+use the_compiler_strikes_back::harness::{ExplorerHandle, PlanetHarness};
+use the_compiler_strikes_back::planet::PlanetBuilder;
+use the_compiler_strikes_back::strategy;
+
+/*
+   Random interleavings of orchestrator and explorer messages against `create_planet`.
+
+   Combination inputs are generated by a supplier planet with its own minimal AI,
+   since resources can only be made by a planet. The explorer stays on the planet for the whole run, so every
+   message must get exactly one response.
+*/
+
+const EXPLORER: u32 = 101;
+const SUPPLIER_EXPLORER: u32 = 999;
+
+#[derive(Debug, Clone)]
+enum Op {
+    Start,
+    Stop,
+    Sunray,
+    Asteroid,
+    InternalState,
+    SupportedResources,
+    SupportedCombinations,
+    Generate(BasicResourceType),
+    AvailableEnergyCell,
+    /// Supported by our planet, fails without a charged cell
+    CombineDiamond,
+    /// Never supported by our planet
+    CombineWater,
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        1 => Just(Op::Start),
+        1 => Just(Op::Stop),
+        4 => Just(Op::Sunray),
+        2 => Just(Op::Asteroid),
+        2 => Just(Op::InternalState),
+        1 => Just(Op::SupportedResources),
+        1 => Just(Op::SupportedCombinations),
+        3 => prop_oneof![
+            Just(BasicResourceType::Silicon),
+            Just(BasicResourceType::Carbon),
+            Just(BasicResourceType::Oxygen),
+            Just(BasicResourceType::Hydrogen),
+        ]
+        .prop_map(Op::Generate),
+        2 => Just(Op::AvailableEnergyCell),
+        2 => Just(Op::CombineDiamond),
+        1 => Just(Op::CombineWater),
+    ]
+}
+
+/// AI of the supplier planet: it only generates basic resources
+struct SupplierAI;
+
+impl PlanetAI for SupplierAI {
+    fn handle_sunray(
+        &mut self,
+        state: &mut PlanetState,
+        _generator: &Generator,
+        _combinator: &Combinator,
+        sunray: Sunray,
+    ) {
+        state.charge_cell(sunray);
+    }
+
+    fn handle_asteroid(
+        &mut self,
+        _state: &mut PlanetState,
+        _generator: &Generator,
+        _combinator: &Combinator,
+    ) -> Option<Rocket> {
+        None
+    }
+
+    fn handle_internal_state_req(
+        &mut self,
+        state: &mut PlanetState,
+        _generator: &Generator,
+        _combinator: &Combinator,
+    ) -> DummyPlanetState {
+        state.to_dummy()
+    }
+
+    fn handle_explorer_msg(
+        &mut self,
+        state: &mut PlanetState,
+        generator: &Generator,
+        _combinator: &Combinator,
+        msg: ExplorerToPlanet,
+    ) -> Option<PlanetToExplorer> {
+        let ExplorerToPlanet::GenerateResourceRequest { resource, .. } = msg else {
+            return None;
+        };
+        let resource = state
+            .full_cell()
+            .and_then(|(cell, _)| generator.try_make(resource, cell).ok());
+        Some(PlanetToExplorer::GenerateResourceResponse { resource })
+    }
+}
+
+/// Planet generating the inputs of the combinations
+struct Supplier {
+    harness: PlanetHarness,
+    explorer: ExplorerHandle,
+}
+
+impl Supplier {
+    fn new() -> Self {
+        let harness = PlanetHarness::spawn_custom(2, |rx_orch, tx_orch, rx_explorer| {
+            Planet::new(
+                2,
+                PlanetType::D,
+                Box::new(SupplierAI),
+                vec![
+                    BasicResourceType::Carbon,
+                    BasicResourceType::Hydrogen,
+                    BasicResourceType::Oxygen,
+                ],
+                vec![],
+                (rx_orch, tx_orch),
+                rx_explorer,
+            )
+            .unwrap()
+        })
+        .unwrap();
+        let explorer = harness.attach_explorer(SUPPLIER_EXPLORER).unwrap();
+        Self { harness, explorer }
+    }
+
+    fn supply(&self, resource: BasicResourceType) -> GenericResource {
+        self.harness.send_sunray().unwrap();
+        match self
+            .explorer
+            .request(ExplorerToPlanet::GenerateResourceRequest {
+                explorer_id: SUPPLIER_EXPLORER,
+                resource,
+            })
+            .unwrap()
+        {
+            PlanetToExplorer::GenerateResourceResponse {
+                resource: Some(resource),
+            } => GenericResource::BasicResources(resource),
+            other => panic!("Unattended message {other:?}"),
+        }
+    }
+
+    fn combination(&self, water: bool) -> (ComplexResourceRequest, [ResourceType; 2]) {
+        use BasicResourceType::{Carbon, Hydrogen, Oxygen};
+
+        if water {
+            let hydrogen = self.supply(Hydrogen).to_hydrogen().unwrap();
+            let oxygen = self.supply(Oxygen).to_oxygen().unwrap();
+            (
+                ComplexResourceRequest::Water(hydrogen, oxygen),
+                [ResourceType::Basic(Hydrogen), ResourceType::Basic(Oxygen)],
+            )
+        } else {
+            let carbon1 = self.supply(Carbon).to_carbon().unwrap();
+            let carbon2 = self.supply(Carbon).to_carbon().unwrap();
+            (
+                ComplexResourceRequest::Diamond(carbon1, carbon2),
+                [ResourceType::Basic(Carbon), ResourceType::Basic(Carbon)],
+            )
+        }
+    }
+}
+
+/// Send an orchestrator message and check its single response
+fn orchestrate(harness: &PlanetHarness, running: &mut bool, op: &Op) -> Result<(), TestCaseError> {
+    let msg = match op {
+        Op::Start => OrchestratorToPlanet::StartPlanetAI,
+        Op::Stop => OrchestratorToPlanet::StopPlanetAI,
+        Op::Sunray => OrchestratorToPlanet::Sunray(Sunray::default()),
+        Op::Asteroid => OrchestratorToPlanet::Asteroid(Asteroid::default()),
+        _ => OrchestratorToPlanet::InternalStateRequest,
+    };
+    let response = harness.request(msg).unwrap();
+
+    if !*running {
+        if let Op::Start = op {
+            prop_assert!(
+                matches!(response, PlanetToOrchestrator::StartPlanetAIResult { .. }),
+                "{:?}",
+                response
+            );
+            *running = true;
+        } else {
+            prop_assert!(
+                matches!(response, PlanetToOrchestrator::Stopped { .. }),
+                "{:?}",
+                response
+            );
+        }
+        return Ok(());
+    }
+    match (op, response) {
+        (Op::Stop, PlanetToOrchestrator::StopPlanetAIResult { .. }) => *running = false,
+        (Op::Sunray, PlanetToOrchestrator::SunrayAck { .. }) => {}
+        (Op::Asteroid, PlanetToOrchestrator::AsteroidAck { .. }) => {}
+        (Op::InternalState, PlanetToOrchestrator::InternalStateResponse { planet_state, .. }) => {
+            //charged cells never exceed capacity
+            let charged = planet_state.energy_cells.iter().filter(|c| **c).count();
+            prop_assert_eq!(planet_state.charged_cells_count, charged);
+            prop_assert!(planet_state.charged_cells_count <= planet_state.energy_cells.len());
+        }
+        (op, response) => prop_assert!(false, "{op:?} answered with {response:?}"),
+    }
+    Ok(())
+}
+
+/// Send an explorer message and check its single response
+fn explore(
+    explorer: &ExplorerHandle,
+    supplier: &Supplier,
+    running: bool,
+    op: &Op,
+) -> Result<(), TestCaseError> {
+    let mut inputs = None;
+    let msg = match op {
+        Op::SupportedResources => ExplorerToPlanet::SupportedResourceRequest {
+            explorer_id: EXPLORER,
+        },
+        Op::SupportedCombinations => ExplorerToPlanet::SupportedCombinationRequest {
+            explorer_id: EXPLORER,
+        },
+        Op::Generate(resource) => ExplorerToPlanet::GenerateResourceRequest {
+            explorer_id: EXPLORER,
+            resource: *resource,
+        },
+        Op::AvailableEnergyCell => ExplorerToPlanet::AvailableEnergyCellRequest {
+            explorer_id: EXPLORER,
+        },
+        _ => {
+            let (msg, types) = supplier.combination(matches!(op, Op::CombineWater));
+            inputs = Some(types);
+            ExplorerToPlanet::CombineResourceRequest {
+                explorer_id: EXPLORER,
+                msg,
+            }
+        }
+    };
+    let response = explorer.request(msg).unwrap();
+
+    if !running {
+        prop_assert!(
+            matches!(response, PlanetToExplorer::Stopped),
+            "{:?}",
+            response
+        );
+        return Ok(());
+    }
+    match (op, response) {
+        (Op::SupportedResources, PlanetToExplorer::SupportedResourceResponse { .. }) => {}
+        (Op::SupportedCombinations, PlanetToExplorer::SupportedCombinationResponse { .. }) => {}
+        (Op::Generate(resource), PlanetToExplorer::GenerateResourceResponse { resource: r }) => {
+            if let Some(r) = r {
+                prop_assert_eq!(r.get_type(), *resource);
+            }
+        }
+        (Op::AvailableEnergyCell, PlanetToExplorer::AvailableEnergyCellResponse { .. }) => {}
+        (_, PlanetToExplorer::CombineResourceResponse { complex_response }) => {
+            if let Err((_, r1, r2)) = complex_response {
+                //resources passed to a failing combination are always returned
+                prop_assert_eq!([r1.get_type(), r2.get_type()], inputs.unwrap());
+            } else {
+                prop_assert!(matches!(op, Op::CombineDiamond), "{:?}", op);
+            }
+        }
+        (op, response) => prop_assert!(false, "{op:?} answered with {response:?}"),
+    }
+    Ok(())
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    //test for random interleavings of orchestrator and explorer messages
+    #[test]
+    fn test_protocol_interleavings(
+        strategy_name in prop_oneof![
+            Just("defensive"),
+            Just("economic"),
+            Just("balanced"),
+            Just("adaptive"),
+        ],
+        ops in prop::collection::vec(op(), 1..40),
+    ) {
+        let audit = ConservationAudit::new();
+        let harness = PlanetHarness::spawn_with(
+            1,
+            PlanetBuilder::new(1)
+                .strategy(strategy::by_name(strategy_name).unwrap())
+                .conservation_audit(audit.clone()),
+        )
+        .unwrap();
+        let explorer = harness.attach_explorer(EXPLORER).unwrap();
+        let supplier = Supplier::new();
+        let mut running = true;
+
+        for op in &ops {
+            match op {
+                // a running planet doesn't answer StartPlanetAI
+                Op::Start if running => continue,
+                Op::Start | Op::Stop | Op::Sunray | Op::Asteroid | Op::InternalState => {
+                    orchestrate(&harness, &mut running, op)?
+                }
+                _ => explore(&explorer, &supplier, running, op)?,
+            }
+            //every request gets exactly one response
+            prop_assert!(harness.try_recv().is_none());
+            prop_assert!(explorer.try_recv().is_none());
+        }
+        //every combination input was either consumed or handed back
+        let report = audit.report();
+        prop_assert!(report.is_balanced(), "{:?}", report.violations);
+        prop_assert_eq!(report.held(), 0);
+        prop_assert_eq!(report.received, report.consumed + report.returned);
+    }
+}
@@ -0,0 +1,182 @@
+use common_game::components::planet::PlanetType;
+use common_game::components::resource::BasicResourceType;
+use common_game::protocols::planet_explorer::{ExplorerToPlanet, PlanetToExplorer};
+use the_compiler_strikes_back::DefenseReserve;
+use the_compiler_strikes_back::events::{EventLog, PlanetEvent};
+use the_compiler_strikes_back::harness::PlanetHarness;
+use the_compiler_strikes_back::planet::PlanetBuilder;
+use the_compiler_strikes_back::queue::QueueConfig;
+use the_compiler_strikes_back::strategy::Defensive;
+
+const SILICON: BasicResourceType = BasicResourceType::Silicon;
+
+// type C planet with its only cell charged and a rocket ready
+fn spawn_charged(builder: PlanetBuilder) -> PlanetHarness {
+    let harness = PlanetHarness::spawn_with(
+        1,
+        builder
+            .strategy(Box::new(Defensive))
+            .defense_reserve(DefenseReserve::Unreserved),
+    )
+    .unwrap();
+    harness.send_sunray().unwrap(); // charged, then used for the rocket
+    harness.send_sunray().unwrap();
+    harness
+}
+
+//test for a reserved cell kept away from the rocket builder and from other explorers
+#[test]
+fn test_reservation_honored() {
+    let event_log = EventLog::new(64);
+    let harness = PlanetHarness::spawn_with(
+        1,
+        PlanetBuilder::new(1)
+            .planet_type(PlanetType::A)
+            .comb_rules(vec![])
+            .strategy(Box::new(Defensive))
+            .defense_reserve(DefenseReserve::Unreserved)
+            .cell_reservations(3)
+            .event_log(event_log.clone()),
+    )
+    .unwrap();
+    let first = harness.attach_explorer(101).unwrap();
+    let second = harness.attach_explorer(102).unwrap();
+
+    harness.send_sunray().unwrap(); // charged, then used for the rocket
+    harness.send_sunray().unwrap();
+    assert!(harness.send_asteroid().unwrap().is_some());
+    assert_eq!(first.available_cells().unwrap(), 1);
+
+    // the defensive strategy leaves the reserved cell alone
+    assert!(!harness.request_state().unwrap().has_rocket);
+    harness.send_sunray().unwrap();
+    assert!(harness.request_state().unwrap().has_rocket);
+    assert_eq!(second.available_cells().unwrap(), 0);
+    assert!(second.generate(SILICON).unwrap().is_none());
+    assert!(first.generate(SILICON).unwrap().is_some());
+
+    let events: Vec<PlanetEvent> = event_log
+        .for_explorer(101)
+        .into_iter()
+        .map(|e| e.event)
+        .collect();
+    assert!(events.contains(&PlanetEvent::CellReserved { explorer_id: 101 }));
+}
+
+//test for a reservation expiring after the given planet events
+#[test]
+fn test_reservation_expired() {
+    let event_log = EventLog::new(64);
+    let harness = PlanetHarness::spawn_with(
+        1,
+        PlanetBuilder::new(1)
+            .planet_type(PlanetType::A)
+            .comb_rules(vec![])
+            .strategy(Box::new(Defensive))
+            .defense_reserve(DefenseReserve::Unreserved)
+            .cell_reservations(1)
+            .event_log(event_log.clone()),
+    )
+    .unwrap();
+    let first = harness.attach_explorer(101).unwrap();
+    let second = harness.attach_explorer(102).unwrap();
+
+    harness.send_sunray().unwrap(); // charged, then used for the rocket
+    harness.send_sunray().unwrap();
+    assert!(harness.send_asteroid().unwrap().is_some());
+    assert_eq!(first.available_cells().unwrap(), 1);
+    assert!(second.generate(SILICON).unwrap().is_none());
+
+    // the reservation expires, the sunray charges a second cell and rebuilds the rocket
+    harness.send_sunray().unwrap();
+    assert!(
+        event_log
+            .events()
+            .iter()
+            .any(|e| e.event == PlanetEvent::ReservationExpired { explorer_id: 101 })
+    );
+    assert!(second.generate(SILICON).unwrap().is_some());
+    assert!(first.generate(SILICON).unwrap().is_none());
+}
+
+//test for a request blocked by a reservation waiting in the queue until the reservation ends
+#[test]
+fn test_reservation_blocked_request_waits() {
+    let harness = spawn_charged(PlanetBuilder::new(1).cell_reservations(3).request_queue(
+        QueueConfig {
+            max_len: 2,
+            timeout_sunrays: 5,
+        },
+    ));
+    let first = harness.attach_explorer(101).unwrap();
+    let second = harness.attach_explorer(102).unwrap();
+    assert_eq!(first.available_cells().unwrap(), 1);
+
+    second
+        .send(ExplorerToPlanet::GenerateResourceRequest {
+            explorer_id: 102,
+            resource: SILICON,
+        })
+        .unwrap();
+    for _ in 0..2 {
+        harness.send_sunray().unwrap();
+        assert!(second.try_recv().is_none());
+    }
+    // the third planet event ends the reservation and the parked request is served
+    harness.send_sunray().unwrap();
+    assert!(matches!(
+        second.recv().unwrap(),
+        PlanetToExplorer::GenerateResourceResponse { resource: Some(_) }
+    ));
+}
+
+//test for a reservation that asking again doesn't renew
+#[test]
+fn test_reservation_not_renewed() {
+    let event_log = EventLog::new(64);
+    let harness = spawn_charged(
+        PlanetBuilder::new(1)
+            .cell_reservations(2)
+            .event_log(event_log.clone()),
+    );
+    let first = harness.attach_explorer(101).unwrap();
+    let second = harness.attach_explorer(102).unwrap();
+
+    assert_eq!(first.available_cells().unwrap(), 1);
+    harness.send_sunray().unwrap();
+    assert_eq!(first.available_cells().unwrap(), 1);
+    harness.send_sunray().unwrap();
+
+    // the reservation expired two events after the first offer
+    assert!(second.generate(SILICON).unwrap().is_some());
+    let reserved = event_log
+        .events()
+        .iter()
+        .filter(|e| e.event == PlanetEvent::CellReserved { explorer_id: 101 })
+        .count();
+    assert_eq!(reserved, 1);
+}
+
+//test for an asteroid turning a reserved cell into a rocket, dropping the reservation
+#[test]
+fn test_reservation_dropped_by_asteroid() {
+    let event_log = EventLog::new(64);
+    let harness = spawn_charged(
+        PlanetBuilder::new(1)
+            .cell_reservations(3)
+            .event_log(event_log.clone()),
+    );
+    let explorer = harness.attach_explorer(101).unwrap();
+    assert_eq!(explorer.available_cells().unwrap(), 1);
+
+    assert!(harness.send_asteroid().unwrap().is_some());
+    // the planet defends itself with the held cell
+    assert!(harness.send_asteroid().unwrap().is_some());
+    assert!(
+        event_log
+            .events()
+            .iter()
+            .any(|e| e.event == PlanetEvent::ReservationDropped { explorer_id: 101 })
+    );
+    assert!(explorer.generate(SILICON).unwrap().is_none());
+}
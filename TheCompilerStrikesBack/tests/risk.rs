@@ -0,0 +1,47 @@
+use the_compiler_strikes_back::harness::PlanetHarness;
+use the_compiler_strikes_back::planet::PlanetBuilder;
+use the_compiler_strikes_back::risk::AsteroidRisk;
+use the_compiler_strikes_back::strategy::Adaptive;
+
+//test for the moving average of the asteroid risk
+#[test]
+fn test_asteroid_risk_estimate() {
+    let mut risk = AsteroidRisk::new(0.5, 0.0);
+    risk.record_asteroid();
+    assert_eq!(risk.estimate(), 0.5);
+    risk.record_asteroid();
+    assert_eq!(risk.estimate(), 0.75);
+    risk.record_sunray();
+    assert_eq!(risk.estimate(), 0.375);
+
+    let mut risk = AsteroidRisk::default();
+    for _ in 0..50 {
+        risk.record_sunray();
+    }
+    assert!(risk.estimate() < 0.001);
+}
+
+//test for the adaptive strategy: rockets are built in advance only after an asteroid spike
+#[test]
+fn test_adaptive_strategy() {
+    let harness = PlanetHarness::spawn_with(
+        1,
+        PlanetBuilder::new(1)
+            .strategy(Box::new(Adaptive::default()))
+            .asteroid_risk(AsteroidRisk::new(0.2, 0.1)),
+    )
+    .unwrap();
+
+    harness.send_sunray().unwrap(); // risk 0.08: the cell goes to explorers
+    let planet_state = harness.request_state().unwrap();
+    assert!(!planet_state.has_rocket);
+    assert_eq!(planet_state.charged_cells_count, 1);
+
+    assert!(harness.send_asteroid().unwrap().is_some()); // risk 0.264
+    assert!(harness.send_asteroid().unwrap().is_none()); // risk 0.411
+
+    harness.send_sunray().unwrap(); // risk 0.329: the cell becomes a rocket
+    let planet_state = harness.request_state().unwrap();
+    assert!(planet_state.has_rocket);
+    assert_eq!(planet_state.charged_cells_count, 0);
+}
@@ -0,0 +1,129 @@
+use common_game::components::resource::BasicResourceType;
+use common_game::protocols::planet_explorer::ExplorerToPlanet;
+use the_compiler_strikes_back::events::{EventLog, PlanetEvent};
+use the_compiler_strikes_back::harness::PlanetHarness;
+use the_compiler_strikes_back::planet::PlanetBuilder;
+use the_compiler_strikes_back::sessions::{MAX_CLOSED_SESSIONS, SessionRegistry};
+use the_compiler_strikes_back::strategy::Economic;
+
+//test for a session opened on arrival and summarized on departure
+#[test]
+fn test_sessions_lifecycle() {
+    let sessions = SessionRegistry::new();
+    let event_log = EventLog::new(64);
+    let harness = PlanetHarness::spawn_with(
+        1,
+        PlanetBuilder::new(1)
+            .strategy(Box::new(Economic))
+            .sessions(sessions.clone())
+            .event_log(event_log.clone()),
+    )
+    .unwrap();
+    let explorer = harness.attach_explorer(101).unwrap();
+    assert!(sessions.session(101).is_some());
+
+    harness.send_sunray().unwrap();
+    for resource in [BasicResourceType::Silicon, BasicResourceType::Silicon] {
+        explorer
+            .request(ExplorerToPlanet::GenerateResourceRequest {
+                explorer_id: 101,
+                resource,
+            })
+            .unwrap();
+    }
+    explorer
+        .request(ExplorerToPlanet::SupportedResourceRequest { explorer_id: 101 })
+        .unwrap();
+
+    let open = sessions.session(101).unwrap();
+    assert_eq!(open.requests, 3);
+    assert_eq!(open.resources_handed_out, 1);
+    assert_eq!(open.departed_unix, None);
+
+    harness.detach_explorer(101).unwrap();
+    assert!(sessions.session(101).is_none());
+    assert!(sessions.open_sessions().is_empty());
+    let closed = sessions.closed_sessions();
+    assert_eq!(closed.len(), 1);
+    assert_eq!(closed[0].requests, 3);
+    assert!(closed[0].departed_unix.is_some());
+
+    let events: Vec<PlanetEvent> = event_log
+        .for_explorer(101)
+        .into_iter()
+        .map(|e| e.event)
+        .collect();
+    assert_eq!(
+        events.first(),
+        Some(&PlanetEvent::ExplorerArrived { explorer_id: 101 })
+    );
+    assert_eq!(
+        events.last(),
+        Some(&PlanetEvent::ExplorerLeft {
+            explorer_id: 101,
+            requests: 3,
+            resources_handed_out: 1,
+        })
+    );
+}
+
+//test for the requests of an explorer that left, which aren't served nor counted
+#[test]
+fn test_sessions_departed_explorer_ignored() {
+    let sessions = SessionRegistry::new();
+    let event_log = EventLog::new(64);
+    let harness = PlanetHarness::spawn_with(
+        1,
+        PlanetBuilder::new(1)
+            .strategy(Box::new(Economic))
+            .sessions(sessions.clone())
+            .event_log(event_log.clone()),
+    )
+    .unwrap();
+    let explorer = harness.attach_explorer(101).unwrap();
+    harness.send_sunray().unwrap();
+    harness.detach_explorer(101).unwrap();
+
+    explorer
+        .send(ExplorerToPlanet::GenerateResourceRequest {
+            explorer_id: 101,
+            resource: BasicResourceType::Silicon,
+        })
+        .unwrap();
+    // the planet handles the explorer messages in order: once another explorer is answered,
+    // the request of 101 was handled
+    let other = harness.attach_explorer(102).unwrap();
+    assert!(
+        other
+            .generate(BasicResourceType::Silicon)
+            .unwrap()
+            .is_some()
+    );
+    assert!(explorer.try_recv().is_none());
+
+    assert_eq!(sessions.closed_sessions()[0].requests, 0);
+    assert!(
+        !event_log
+            .for_explorer(101)
+            .iter()
+            .any(|e| matches!(e.event, PlanetEvent::RequestServed { .. }))
+    );
+}
+
+//test for the closed sessions kept up to a bound, dropping the oldest
+#[test]
+fn test_sessions_closed_bounded() {
+    let sessions = SessionRegistry::new();
+    let harness =
+        PlanetHarness::spawn_with(1, PlanetBuilder::new(1).sessions(sessions.clone())).unwrap();
+    let visits = MAX_CLOSED_SESSIONS as u32 + 2;
+    for explorer_id in 0..visits {
+        harness.attach_explorer(explorer_id).unwrap();
+        harness.detach_explorer(explorer_id).unwrap();
+    }
+
+    let closed = sessions.closed_sessions();
+    assert_eq!(closed.len(), MAX_CLOSED_SESSIONS);
+    assert_eq!(closed[0].explorer_id, 2);
+    assert_eq!(closed.last().unwrap().explorer_id, visits - 1);
+}
@@ -0,0 +1,129 @@
+use common_game::components::planet::PlanetType;
+use common_game::components::resource::{BasicResourceType, ComplexResourceType, ResourceType};
+use the_compiler_strikes_back::harness::PlanetHarness;
+use the_compiler_strikes_back::planet::PlanetBuilder;
+use the_compiler_strikes_back::sim_explorer::{Goal, SimExplorer};
+use the_compiler_strikes_back::strategy::Economic;
+
+//test for a trade session on our planet: Silicon is obtained, the AIPartner chain stops at Life
+#[test]
+fn test_sim_explorer_ai_partner_chain() {
+    let harness =
+        PlanetHarness::spawn_with(1, PlanetBuilder::new(1).strategy(Box::new(Economic))).unwrap();
+    harness.send_sunray().unwrap();
+
+    let mut explorer = SimExplorer::new(101);
+    let report = explorer
+        .visit(
+            &harness,
+            &[
+                Goal::Generate(BasicResourceType::Silicon),
+                Goal::Build(ComplexResourceType::AIPartner),
+            ],
+        )
+        .unwrap();
+
+    assert!(
+        report
+            .supported_resources
+            .contains(&BasicResourceType::Silicon)
+    );
+    assert!(
+        report
+            .supported_combinations
+            .contains(&ComplexResourceType::AIPartner)
+    );
+    assert_eq!(
+        report.obtained,
+        vec![ResourceType::Basic(BasicResourceType::Silicon)]
+    );
+
+    // Life can't be combined on our planet, so the Robot and the AIPartner can't be built
+    let failed: Vec<ResourceType> = report.failures.iter().map(|f| f.resource).collect();
+    assert_eq!(
+        failed,
+        vec![
+            ResourceType::Complex(ComplexResourceType::Life),
+            ResourceType::Complex(ComplexResourceType::Robot),
+            ResourceType::Complex(ComplexResourceType::AIPartner),
+        ]
+    );
+
+    // the Silicon taken for the Robot is back in the inventory
+    assert_eq!(
+        explorer.count(ResourceType::Basic(BasicResourceType::Silicon)),
+        1
+    );
+}
+
+//test for a generation refused because no cell is charged
+#[test]
+fn test_sim_explorer_no_charge() {
+    let harness = PlanetHarness::spawn_with(1, PlanetBuilder::new(1)).unwrap();
+
+    let mut explorer = SimExplorer::new(101);
+    let report = explorer
+        .visit(&harness, &[Goal::Generate(BasicResourceType::Silicon)])
+        .unwrap();
+
+    assert!(report.obtained.is_empty());
+    assert_eq!(report.failures.len(), 1);
+    assert_eq!(report.failures[0].reason, "generation refused");
+    assert!(explorer.inventory().is_empty());
+}
+
+//test for the AIPartner chain built step by step: Silicon, then the Robot, then the AIPartner
+#[test]
+fn test_sim_explorer_ai_partner_built() {
+    use BasicResourceType::{Carbon, Hydrogen, Oxygen, Silicon};
+    use ComplexResourceType::{AIPartner, Diamond, Life, Robot, Water};
+
+    let mut explorer = SimExplorer::new(101);
+
+    // the basic resources our planet doesn't generate come from a type D planet
+    let supplier = PlanetHarness::spawn_with(
+        2,
+        PlanetBuilder::of_type(2, PlanetType::D, vec![Carbon, Hydrogen, Oxygen], vec![])
+            .strategy(Box::new(Economic)),
+    )
+    .unwrap();
+    for _ in 0..5 {
+        supplier.send_sunray().unwrap();
+    }
+    let goals = [Carbon, Carbon, Carbon, Hydrogen, Oxygen].map(Goal::Generate);
+    let report = explorer.visit(&supplier, &goals).unwrap();
+    assert!(report.failures.is_empty(), "{:?}", report.failures);
+
+    // one cell per step, charged before each of them
+    let harness = PlanetHarness::spawn_with(
+        1,
+        PlanetBuilder::of_type(
+            1,
+            PlanetType::C,
+            vec![Silicon],
+            vec![Water, Life, Diamond, Robot, AIPartner],
+        )
+        .strategy(Box::new(Economic)),
+    )
+    .unwrap();
+    explorer.arrive(&harness).unwrap();
+    for goal in [
+        Goal::Build(Water),
+        Goal::Build(Life),
+        Goal::Build(Diamond),
+        Goal::Generate(Silicon),
+        Goal::Build(Robot),
+        Goal::Build(AIPartner),
+    ] {
+        harness.send_sunray().unwrap();
+        let report = explorer.run(&[goal]).unwrap();
+        assert!(report.failures.is_empty(), "{:?}", report.failures);
+        assert_eq!(report.obtained.len(), 1);
+    }
+    explorer.leave(&harness).unwrap();
+
+    // every intermediate resource went into the AIPartner handed over by the planet
+    let inventory = explorer.take_inventory();
+    assert_eq!(inventory.len(), 1);
+    assert_eq!(inventory[0].get_type(), ResourceType::Complex(AIPartner));
+}
@@ -0,0 +1,209 @@
+use common_game::components::planet::PlanetType;
+use common_game::components::resource::BasicResourceType;
+use common_game::components::resource::ComplexResourceType::{AIPartner, Diamond, Robot};
+use common_game::protocols::orchestrator_planet::{OrchestratorToPlanet, PlanetToOrchestrator};
+use crossbeam_channel::unbounded;
+use std::thread;
+use the_compiler_strikes_back::events::{EventLog, PlanetEvent};
+use the_compiler_strikes_back::harness::PlanetHarness;
+use the_compiler_strikes_back::planet::{PlanetBuilder, create_planet_from_snapshot};
+use the_compiler_strikes_back::snapshot::PlanetSnapshot;
+use the_compiler_strikes_back::strategy::Economic;
+
+//test for the snapshot written when the planet AI is stopped
+#[test]
+fn test_snapshot_on_stop() {
+    let path = std::env::temp_dir().join("the_compiler_strikes_back_snapshot.json");
+    let _ = std::fs::remove_file(&path);
+    let harness = PlanetHarness::spawn_with(
+        1,
+        PlanetBuilder::new(1)
+            .strategy(Box::new(Economic))
+            .snapshot_path(&path),
+    )
+    .unwrap();
+
+    harness.send_sunray().unwrap();
+    harness.send_sunray().unwrap(); // wasted
+    harness.stop().unwrap();
+    // on_stop runs after the ack: a stopped planet answers the next message once it's done
+    assert!(harness.send_sunray().is_err());
+
+    let snapshot = PlanetSnapshot::load(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(
+        snapshot,
+        PlanetSnapshot {
+            planet_id: 1,
+            strategy: "economic".to_string(),
+            planet_type: "C".to_string(),
+            gen_rules: vec![BasicResourceType::Silicon],
+            comb_rules: vec![AIPartner, Diamond, Robot],
+            energy_cells: vec![true],
+            has_rocket: false,
+            wasted_sunrays: 1,
+            failed_rocket_builds: 0,
+        }
+    );
+    assert_eq!(
+        PlanetSnapshot::from_json(&snapshot.to_json()).unwrap(),
+        snapshot
+    );
+}
+
+//test for a planet recreated from a snapshot with its type, a rocket and a charged cell
+#[test]
+fn test_create_planet_from_snapshot() {
+    let snapshot = PlanetSnapshot {
+        planet_id: 2,
+        strategy: "defensive".to_string(),
+        planet_type: "A".to_string(),
+        gen_rules: vec![BasicResourceType::Carbon],
+        comb_rules: vec![],
+        energy_cells: vec![true, false, false, false, false],
+        has_rocket: true,
+        wasted_sunrays: 3,
+        failed_rocket_builds: 0,
+    };
+    let (tx_orch, rx_planet) = unbounded();
+    let (tx_planet, rx_orch) = unbounded();
+    let (_tx_explorer, rx_explorer) = unbounded();
+    let mut planet =
+        create_planet_from_snapshot(rx_planet, tx_planet, rx_explorer, snapshot.clone()).unwrap();
+    assert!(matches!(planet.planet_type(), PlanetType::A));
+    assert!(planet.generator().contains(BasicResourceType::Carbon));
+    let handle = thread::spawn(move || planet.run());
+
+    tx_orch.send(OrchestratorToPlanet::StartPlanetAI).unwrap();
+    rx_orch.recv().unwrap();
+    tx_orch
+        .send(OrchestratorToPlanet::InternalStateRequest)
+        .unwrap();
+    match rx_orch.recv().unwrap() {
+        PlanetToOrchestrator::InternalStateResponse { planet_state, .. } => {
+            assert!(planet_state.has_rocket);
+            assert_eq!(planet_state.energy_cells, snapshot.energy_cells);
+            assert_eq!(planet_state.charged_cells_count, 1);
+        }
+        _ => panic!("unexpected response"),
+    }
+
+    tx_orch.send(OrchestratorToPlanet::KillPlanet).unwrap();
+    rx_orch.recv().unwrap();
+    assert!(handle.join().unwrap().is_ok());
+
+    let unknown = PlanetSnapshot {
+        strategy: "reckless".to_string(),
+        ..snapshot.clone()
+    };
+    let (_tx, rx_planet) = unbounded();
+    let (tx_planet, _rx) = unbounded();
+    let (_tx_explorer, rx_explorer) = unbounded();
+    assert!(create_planet_from_snapshot(rx_planet, tx_planet, rx_explorer, unknown).is_err());
+
+    let unknown = PlanetSnapshot {
+        planet_type: "E".to_string(),
+        ..snapshot
+    };
+    let (_tx, rx_planet) = unbounded();
+    let (tx_planet, _rx) = unbounded();
+    let (_tx_explorer, rx_explorer) = unbounded();
+    assert!(create_planet_from_snapshot(rx_planet, tx_planet, rx_explorer, unknown).is_err());
+}
+
+//test for a planet stopped before its snapshot was restored: the checkpoint is kept
+#[test]
+fn test_snapshot_kept_until_restored() {
+    let path = std::env::temp_dir().join("the_compiler_strikes_back_snapshot_kept.json");
+    let snapshot = PlanetSnapshot {
+        planet_id: 4,
+        strategy: "economic".to_string(),
+        planet_type: "C".to_string(),
+        gen_rules: vec![BasicResourceType::Silicon],
+        comb_rules: vec![AIPartner, Diamond, Robot],
+        energy_cells: vec![true],
+        has_rocket: false,
+        wasted_sunrays: 2,
+        failed_rocket_builds: 0,
+    };
+    snapshot.save(&path).unwrap();
+    let harness = PlanetHarness::spawn_with(
+        4,
+        PlanetBuilder::new(4)
+            .strategy(Box::new(Economic))
+            .restore(snapshot.clone())
+            .snapshot_path(&path),
+    )
+    .unwrap();
+
+    harness.stop().unwrap();
+    assert!(harness.send_sunray().is_err());
+
+    let kept = PlanetSnapshot::load(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(kept, snapshot);
+}
+
+//test for the events of a restored planet: counters resume from the snapshot
+#[test]
+fn test_snapshot_restore_events() {
+    let event_log = EventLog::new(16);
+    let snapshot = PlanetSnapshot {
+        planet_id: 3,
+        strategy: "economic".to_string(),
+        planet_type: "C".to_string(),
+        gen_rules: vec![BasicResourceType::Silicon],
+        comb_rules: vec![AIPartner, Diamond, Robot],
+        energy_cells: vec![true],
+        has_rocket: false,
+        wasted_sunrays: 4,
+        failed_rocket_builds: 0,
+    };
+    let harness = PlanetHarness::spawn_with(
+        3,
+        PlanetBuilder::new(3)
+            .strategy(Box::new(Economic))
+            .restore(snapshot)
+            .event_log(event_log.clone()),
+    )
+    .unwrap();
+
+    harness.send_sunray().unwrap();
+
+    let events: Vec<PlanetEvent> = event_log.events().into_iter().map(|e| e.event).collect();
+    assert_eq!(
+        events,
+        vec![
+            PlanetEvent::SnapshotRestored,
+            PlanetEvent::CellWasted { total_wasted: 5 },
+        ]
+    );
+}
+
+//test for the default strategy: it depends on the planet type, not on how the planet is built
+#[test]
+fn test_snapshot_default_strategy() {
+    for (planet_id, planet_type, strategy) in [
+        (3, PlanetType::A, "defensive"),
+        (4, PlanetType::D, "economic"),
+    ] {
+        let path = std::env::temp_dir().join(format!(
+            "the_compiler_strikes_back_default_strategy_{planet_id}.json"
+        ));
+        let _ = std::fs::remove_file(&path);
+        let harness = PlanetHarness::spawn_with(
+            planet_id,
+            PlanetBuilder::new(planet_id)
+                .planet_type(planet_type)
+                .comb_rules(vec![])
+                .snapshot_path(&path),
+        )
+        .unwrap();
+        harness.stop().unwrap();
+        assert!(harness.send_sunray().is_err());
+
+        let snapshot = PlanetSnapshot::load(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(snapshot.strategy, strategy);
+    }
+}
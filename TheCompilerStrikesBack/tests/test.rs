@@ -1,5 +1,8 @@
+// the original tests compare flags with assert_eq
+#![allow(clippy::bool_assert_comparison)]
+
 use common_game::components::asteroid::Asteroid;
-use common_game::components::planet::Planet;
+use common_game::components::planet::{Planet, PlanetType};
 use common_game::components::resource::ComplexResourceType::{AIPartner, Diamond, Robot};
 use common_game::components::resource::{BasicResourceType, ComplexResourceType};
 use common_game::components::sunray::Sunray;
@@ -8,7 +11,11 @@ use common_game::protocols::planet_explorer::{ExplorerToPlanet, PlanetToExplorer
 use crossbeam_channel::bounded;
 use std::collections::HashSet;
 use std::thread;
+use the_compiler_strikes_back::PlanetCreationError;
+use the_compiler_strikes_back::fairness::FairnessPolicy;
 use the_compiler_strikes_back::planet::*;
+use the_compiler_strikes_back::queue::QueueConfig;
+use the_compiler_strikes_back::strategy::*;
 
 pub fn init_logger() {
     static INIT: std::sync::Once = std::sync::Once::new();
@@ -522,3 +529,520 @@ fn test_planet_explorer() {
     }
     handle.join().unwrap();
 }
+
+//sends a sunray and waits for the ack
+fn send_sunray(
+    tx_orch: &crossbeam_channel::Sender<OrchestratorToPlanet>,
+    rx_orch: &crossbeam_channel::Receiver<PlanetToOrchestrator>,
+) {
+    tx_orch
+        .send(OrchestratorToPlanet::Sunray(Sunray::default()))
+        .unwrap();
+    match rx_orch.recv() {
+        Ok(PlanetToOrchestrator::SunrayAck { .. }) => {}
+        _ => panic!("Expected SunrayAck"),
+    }
+}
+
+//asks the internal state of the planet
+fn request_state(
+    tx_orch: &crossbeam_channel::Sender<OrchestratorToPlanet>,
+    rx_orch: &crossbeam_channel::Receiver<PlanetToOrchestrator>,
+) -> common_game::components::planet::DummyPlanetState {
+    tx_orch
+        .send(OrchestratorToPlanet::InternalStateRequest)
+        .unwrap();
+    match rx_orch.recv() {
+        Ok(PlanetToOrchestrator::InternalStateResponse { planet_state, .. }) => planet_state,
+        _ => panic!("Expected InternalStateResponse"),
+    }
+}
+
+//test for the economic strategy: cells are kept for explorers
+#[test]
+fn test_strategy_economic() {
+    let (tx_orch, rx_planet) = bounded(10);
+    let (tx_planet, rx_orch) = bounded(10);
+    let (_tx_explorer, rx_explorer) = bounded(10);
+
+    let mut planet = PlanetBuilder::new(1)
+        .strategy(Box::new(Economic))
+        .build(rx_planet, tx_planet, rx_explorer)
+        .unwrap();
+    let handle = thread::spawn(move || {
+        planet.run().unwrap();
+    });
+
+    tx_orch.send(OrchestratorToPlanet::StartPlanetAI).unwrap();
+    assert!(matches!(
+        rx_orch.recv(),
+        Ok(PlanetToOrchestrator::StartPlanetAIResult { .. })
+    ));
+
+    send_sunray(&tx_orch, &rx_orch);
+    send_sunray(&tx_orch, &rx_orch);
+    let planet_state = request_state(&tx_orch, &rx_orch);
+    assert!(!planet_state.has_rocket);
+    assert_eq!(planet_state.charged_cells_count, 1);
+
+    // the rocket is still built on demand when an asteroid arrives
+    tx_orch
+        .send(OrchestratorToPlanet::Asteroid(Asteroid::default()))
+        .unwrap();
+    match rx_orch.recv() {
+        Ok(PlanetToOrchestrator::AsteroidAck { rocket, .. }) => assert!(rocket.is_some()),
+        _ => panic!("Expected AsteroidAck"),
+    }
+
+    tx_orch.send(OrchestratorToPlanet::KillPlanet).unwrap();
+    assert!(matches!(
+        rx_orch.recv(),
+        Ok(PlanetToOrchestrator::KillPlanetResult { .. })
+    ));
+    handle.join().unwrap();
+}
+
+//test for the balanced strategy: the rocket is built only when a sunray would be wasted
+#[test]
+fn test_strategy_balanced() {
+    let (tx_orch, rx_planet) = bounded(10);
+    let (tx_planet, rx_orch) = bounded(10);
+    let (_tx_explorer, rx_explorer) = bounded(10);
+
+    let mut planet = PlanetBuilder::new(1)
+        .strategy(Box::new(Balanced))
+        .build(rx_planet, tx_planet, rx_explorer)
+        .unwrap();
+    let handle = thread::spawn(move || {
+        planet.run().unwrap();
+    });
+
+    tx_orch.send(OrchestratorToPlanet::StartPlanetAI).unwrap();
+    assert!(matches!(
+        rx_orch.recv(),
+        Ok(PlanetToOrchestrator::StartPlanetAIResult { .. })
+    ));
+
+    send_sunray(&tx_orch, &rx_orch);
+    let planet_state = request_state(&tx_orch, &rx_orch);
+    assert!(!planet_state.has_rocket);
+    assert_eq!(planet_state.charged_cells_count, 1);
+
+    // the cell is full: the rocket takes it and the sunray recharges it
+    send_sunray(&tx_orch, &rx_orch);
+    let planet_state = request_state(&tx_orch, &rx_orch);
+    assert!(planet_state.has_rocket);
+    assert_eq!(planet_state.charged_cells_count, 1);
+
+    tx_orch.send(OrchestratorToPlanet::KillPlanet).unwrap();
+    assert!(matches!(
+        rx_orch.recv(),
+        Ok(PlanetToOrchestrator::KillPlanetResult { .. })
+    ));
+    handle.join().unwrap();
+}
+
+//test for the planet builder: custom configuration and rejected configuration
+#[test]
+fn test_planet_builder() {
+    let (_tx_orch, rx_planet) = bounded(10);
+    let (tx_planet, _rx_orch) = bounded(10);
+    let (_tx_explorer, rx_explorer) = bounded(10);
+
+    let planet = PlanetBuilder::new(7)
+        .planet_type(PlanetType::C)
+        .gen_rules(vec![BasicResourceType::Silicon])
+        .comb_rules(vec![Robot])
+        .strategy(Box::new(Economic))
+        .build(rx_planet, tx_planet, rx_explorer)
+        .unwrap();
+    assert_eq!(planet.id(), 7);
+    assert!(planet.combinator().contains(Robot));
+    assert!(!planet.combinator().contains(Diamond));
+
+    // type C planets can only have one generation rule
+    let (_tx_orch, rx_planet) = bounded(10);
+    let (tx_planet, _rx_orch) = bounded(10);
+    let (_tx_explorer, rx_explorer) = bounded(10);
+
+    let result = PlanetBuilder::new(8)
+        .gen_rules(vec![BasicResourceType::Silicon, BasicResourceType::Carbon])
+        .build(rx_planet, tx_planet, rx_explorer);
+    assert!(matches!(
+        result,
+        Err(PlanetCreationError::TooManyGenerationRules(_))
+    ));
+}
+
+//test for fallible planet creation
+#[test]
+fn test_try_create_planet() {
+    let (_tx_orch, rx_planet) = bounded(10);
+    let (tx_planet, _rx_orch) = bounded(10);
+    let (_tx_explorer, rx_explorer) = bounded(10);
+
+    let planet = try_create_planet(rx_planet, tx_planet, rx_explorer, 1);
+    assert!(planet.is_ok());
+
+    // type B planets allow a single combination rule
+    let (_tx_orch, rx_planet) = bounded(10);
+    let (tx_planet, _rx_orch) = bounded(10);
+    let (_tx_explorer, rx_explorer) = bounded(10);
+
+    let result = PlanetBuilder::new(2)
+        .planet_type(PlanetType::B)
+        .build(rx_planet, tx_planet, rx_explorer);
+    match result {
+        Err(err @ PlanetCreationError::TooManyCombinationRules(_)) => {
+            assert!(err.reason().contains("limited to 1"));
+        }
+        _ => panic!("Expected TooManyCombinationRules"),
+    }
+
+    let (_tx_orch, rx_planet) = bounded(10);
+    let (tx_planet, _rx_orch) = bounded(10);
+    let (_tx_explorer, rx_explorer) = bounded(10);
+
+    let result = PlanetBuilder::new(3)
+        .gen_rules(vec![])
+        .build(rx_planet, tx_planet, rx_explorer);
+    assert!(matches!(
+        result,
+        Err(PlanetCreationError::EmptyGenerationRules(_))
+    ));
+}
+
+//test for the request queue: requests wait for the next sunray instead of failing
+#[test]
+fn test_request_queue() {
+    let (tx_orch, rx_planet) = bounded(10);
+    let (tx_planet, rx_orch) = bounded(10);
+    let (tx_explorer, rx_explorer) = bounded(10);
+
+    let mut planet = PlanetBuilder::new(1)
+        .strategy(Box::new(Economic))
+        .request_queue(QueueConfig {
+            max_len: 1,
+            timeout_sunrays: 1,
+        })
+        .build(rx_planet, tx_planet, rx_explorer)
+        .unwrap();
+    let handle = thread::spawn(move || {
+        planet.run().unwrap();
+    });
+
+    tx_orch.send(OrchestratorToPlanet::StartPlanetAI).unwrap();
+    assert!(matches!(
+        rx_orch.recv(),
+        Ok(PlanetToOrchestrator::StartPlanetAIResult { .. })
+    ));
+
+    // two explorers arrive on the planet
+    let mut explorers = Vec::new();
+    for explorer_id in [101, 102] {
+        let (expl_tx_local, expl_rx_local) = bounded::<PlanetToExplorer>(10);
+        tx_orch
+            .send(OrchestratorToPlanet::IncomingExplorerRequest {
+                explorer_id,
+                new_sender: expl_tx_local,
+            })
+            .unwrap();
+        assert!(matches!(
+            rx_orch.recv(),
+            Ok(PlanetToOrchestrator::IncomingExplorerResponse { .. })
+        ));
+        explorers.push(expl_rx_local);
+    }
+
+    // no cell is charged: both requests are parked
+    for explorer_id in [101, 102] {
+        tx_explorer
+            .send(ExplorerToPlanet::GenerateResourceRequest {
+                explorer_id,
+                resource: BasicResourceType::Silicon,
+            })
+            .unwrap();
+    }
+    // the queue of explorer 101 is full: the request is refused right away
+    tx_explorer
+        .send(ExplorerToPlanet::GenerateResourceRequest {
+            explorer_id: 101,
+            resource: BasicResourceType::Silicon,
+        })
+        .unwrap();
+    match explorers[0].recv_timeout(std::time::Duration::from_secs(1)) {
+        Ok(PlanetToExplorer::GenerateResourceResponse { resource }) => assert!(resource.is_none()),
+        _ => panic!("Expected GenerateResourceResponse"),
+    }
+    assert!(explorers[1].try_recv().is_err());
+
+    // one sunray: the first request is served, the second one times out
+    send_sunray(&tx_orch, &rx_orch);
+    match explorers[0].recv_timeout(std::time::Duration::from_secs(1)) {
+        Ok(PlanetToExplorer::GenerateResourceResponse { resource }) => {
+            assert_eq!(resource.unwrap().get_type(), BasicResourceType::Silicon)
+        }
+        _ => panic!("Expected GenerateResourceResponse"),
+    }
+    match explorers[1].recv_timeout(std::time::Duration::from_secs(1)) {
+        Ok(PlanetToExplorer::GenerateResourceResponse { resource }) => assert!(resource.is_none()),
+        _ => panic!("Expected GenerateResourceResponse"),
+    }
+
+    tx_orch.send(OrchestratorToPlanet::KillPlanet).unwrap();
+    assert!(matches!(
+        rx_orch.recv(),
+        Ok(PlanetToOrchestrator::KillPlanetResult { .. })
+    ));
+    handle.join().unwrap();
+}
+
+//test for the request queue: requests without a recipe are refused right away instead of parked
+#[test]
+fn test_request_queue_unsupported() {
+    let (tx_orch, rx_planet) = bounded(10);
+    let (tx_planet, rx_orch) = bounded(10);
+    let (tx_explorer, rx_explorer) = bounded(10);
+
+    let mut planet = PlanetBuilder::new(1)
+        .strategy(Box::new(Economic))
+        .request_queue(QueueConfig {
+            max_len: 1,
+            timeout_sunrays: 5,
+        })
+        .build(rx_planet, tx_planet, rx_explorer)
+        .unwrap();
+    let handle = thread::spawn(move || {
+        planet.run().unwrap();
+    });
+
+    tx_orch.send(OrchestratorToPlanet::StartPlanetAI).unwrap();
+    assert!(matches!(
+        rx_orch.recv(),
+        Ok(PlanetToOrchestrator::StartPlanetAIResult { .. })
+    ));
+    let (expl_tx_local, expl_rx_local) = bounded::<PlanetToExplorer>(10);
+    tx_orch
+        .send(OrchestratorToPlanet::IncomingExplorerRequest {
+            explorer_id: 101,
+            new_sender: expl_tx_local,
+        })
+        .unwrap();
+    assert!(matches!(
+        rx_orch.recv(),
+        Ok(PlanetToOrchestrator::IncomingExplorerResponse { .. })
+    ));
+
+    // no cell is charged, but the planet doesn't generate oxygen at all
+    tx_explorer
+        .send(ExplorerToPlanet::GenerateResourceRequest {
+            explorer_id: 101,
+            resource: BasicResourceType::Oxygen,
+        })
+        .unwrap();
+    match expl_rx_local.recv_timeout(std::time::Duration::from_secs(1)) {
+        Ok(PlanetToExplorer::GenerateResourceResponse { resource }) => assert!(resource.is_none()),
+        _ => panic!("Expected GenerateResourceResponse"),
+    }
+
+    tx_orch.send(OrchestratorToPlanet::KillPlanet).unwrap();
+    assert!(matches!(
+        rx_orch.recv(),
+        Ok(PlanetToOrchestrator::KillPlanetResult { .. })
+    ));
+    handle.join().unwrap();
+}
+
+//test for the request queue: the orchestrator relay stops when the planet is killed
+#[test]
+fn test_request_queue_relay_stops() {
+    let (tx_orch, rx_planet) = bounded(10);
+    let (tx_planet, rx_orch) = bounded(10);
+    let (_tx_explorer, rx_explorer) = bounded(10);
+
+    let mut planet = PlanetBuilder::new(1)
+        .request_queue(QueueConfig {
+            max_len: 1,
+            timeout_sunrays: 1,
+        })
+        .build(rx_planet, tx_planet, rx_explorer)
+        .unwrap();
+    let handle = thread::spawn(move || {
+        planet.run().unwrap();
+    });
+
+    tx_orch.send(OrchestratorToPlanet::StartPlanetAI).unwrap();
+    assert!(matches!(
+        rx_orch.recv(),
+        Ok(PlanetToOrchestrator::StartPlanetAIResult { .. })
+    ));
+    tx_orch.send(OrchestratorToPlanet::KillPlanet).unwrap();
+    assert!(matches!(
+        rx_orch.recv(),
+        Ok(PlanetToOrchestrator::KillPlanetResult { .. })
+    ));
+    handle.join().unwrap();
+
+    // the relay dropped the orchestrator channel together with the planet
+    assert!(tx_orch.send(OrchestratorToPlanet::StartPlanetAI).is_err());
+}
+
+//sends a generation request and returns the generated resource type, if any
+fn generate(
+    tx_explorer: &crossbeam_channel::Sender<ExplorerToPlanet>,
+    expl_rx_local: &crossbeam_channel::Receiver<PlanetToExplorer>,
+    explorer_id: u32,
+) -> Option<BasicResourceType> {
+    tx_explorer
+        .send(ExplorerToPlanet::GenerateResourceRequest {
+            explorer_id,
+            resource: BasicResourceType::Silicon,
+        })
+        .unwrap();
+    match expl_rx_local.recv() {
+        Ok(PlanetToExplorer::GenerateResourceResponse { resource }) => {
+            resource.map(|r| r.get_type())
+        }
+        _ => panic!("Expected GenerateResourceResponse"),
+    }
+}
+
+//test for the cell quota: an explorer can't drain every cell, the quota resets on departure
+#[test]
+fn test_fairness_quota() {
+    let (tx_orch, rx_planet) = bounded(10);
+    let (tx_planet, rx_orch) = bounded(10);
+    let (tx_explorer, rx_explorer) = bounded(10);
+
+    let mut planet = PlanetBuilder::new(1)
+        .strategy(Box::new(Economic))
+        .fairness(FairnessPolicy::Quota { cells_per_visit: 1 })
+        .build(rx_planet, tx_planet, rx_explorer)
+        .unwrap();
+    let handle = thread::spawn(move || {
+        planet.run().unwrap();
+    });
+
+    tx_orch.send(OrchestratorToPlanet::StartPlanetAI).unwrap();
+    assert!(matches!(
+        rx_orch.recv(),
+        Ok(PlanetToOrchestrator::StartPlanetAIResult { .. })
+    ));
+
+    let explorer_id = 101;
+    let (expl_tx_local, expl_rx_local) = bounded::<PlanetToExplorer>(10);
+    tx_orch
+        .send(OrchestratorToPlanet::IncomingExplorerRequest {
+            explorer_id,
+            new_sender: expl_tx_local.clone(),
+        })
+        .unwrap();
+    assert!(matches!(
+        rx_orch.recv(),
+        Ok(PlanetToOrchestrator::IncomingExplorerResponse { .. })
+    ));
+
+    send_sunray(&tx_orch, &rx_orch);
+    assert_eq!(
+        generate(&tx_explorer, &expl_rx_local, explorer_id),
+        Some(BasicResourceType::Silicon)
+    );
+
+    // the cell is charged, but the explorer already used its quota
+    send_sunray(&tx_orch, &rx_orch);
+    assert_eq!(generate(&tx_explorer, &expl_rx_local, explorer_id), None);
+    assert_eq!(request_state(&tx_orch, &rx_orch).charged_cells_count, 1);
+
+    // leaving the planet resets the quota
+    tx_orch
+        .send(OrchestratorToPlanet::OutgoingExplorerRequest { explorer_id })
+        .unwrap();
+    assert!(matches!(
+        rx_orch.recv(),
+        Ok(PlanetToOrchestrator::OutgoingExplorerResponse { .. })
+    ));
+    tx_orch
+        .send(OrchestratorToPlanet::IncomingExplorerRequest {
+            explorer_id,
+            new_sender: expl_tx_local,
+        })
+        .unwrap();
+    assert!(matches!(
+        rx_orch.recv(),
+        Ok(PlanetToOrchestrator::IncomingExplorerResponse { .. })
+    ));
+    assert_eq!(
+        generate(&tx_explorer, &expl_rx_local, explorer_id),
+        Some(BasicResourceType::Silicon)
+    );
+
+    tx_orch.send(OrchestratorToPlanet::KillPlanet).unwrap();
+    assert!(matches!(
+        rx_orch.recv(),
+        Ok(PlanetToOrchestrator::KillPlanetResult { .. })
+    ));
+    handle.join().unwrap();
+}
+
+//test for round-robin: two explorers competing for the cells are served in turn, without a queue
+#[test]
+fn test_fairness_round_robin() {
+    let (tx_orch, rx_planet) = bounded(10);
+    let (tx_planet, rx_orch) = bounded(10);
+    let (tx_explorer, rx_explorer) = bounded(10);
+
+    let mut planet = PlanetBuilder::new(1)
+        .strategy(Box::new(Economic))
+        .fairness(FairnessPolicy::RoundRobin)
+        .build(rx_planet, tx_planet, rx_explorer)
+        .unwrap();
+    let handle = thread::spawn(move || {
+        planet.run().unwrap();
+    });
+
+    tx_orch.send(OrchestratorToPlanet::StartPlanetAI).unwrap();
+    assert!(matches!(
+        rx_orch.recv(),
+        Ok(PlanetToOrchestrator::StartPlanetAIResult { .. })
+    ));
+
+    let (first, second) = (101, 102);
+    let (first_tx, first_rx) = bounded::<PlanetToExplorer>(10);
+    let (second_tx, second_rx) = bounded::<PlanetToExplorer>(10);
+    for (explorer_id, new_sender) in [(first, first_tx), (second, second_tx)] {
+        tx_orch
+            .send(OrchestratorToPlanet::IncomingExplorerRequest {
+                explorer_id,
+                new_sender,
+            })
+            .unwrap();
+        assert!(matches!(
+            rx_orch.recv(),
+            Ok(PlanetToOrchestrator::IncomingExplorerResponse { .. })
+        ));
+    }
+
+    send_sunray(&tx_orch, &rx_orch);
+    assert!(generate(&tx_explorer, &first_rx, first).is_some());
+    // both explorers find the cell empty and wait for the next one
+    assert_eq!(generate(&tx_explorer, &first_rx, first), None);
+    assert_eq!(generate(&tx_explorer, &second_rx, second), None);
+
+    // the first explorer asks first, but the cell goes to the second one
+    send_sunray(&tx_orch, &rx_orch);
+    assert_eq!(generate(&tx_explorer, &first_rx, first), None);
+    assert_eq!(request_state(&tx_orch, &rx_orch).charged_cells_count, 1);
+    assert!(generate(&tx_explorer, &second_rx, second).is_some());
+
+    // and now it's the first explorer's turn
+    send_sunray(&tx_orch, &rx_orch);
+    assert_eq!(generate(&tx_explorer, &second_rx, second), None);
+    assert!(generate(&tx_explorer, &first_rx, first).is_some());
+
+    tx_orch.send(OrchestratorToPlanet::KillPlanet).unwrap();
+    assert!(matches!(
+        rx_orch.recv(),
+        Ok(PlanetToOrchestrator::KillPlanetResult { .. })
+    ));
+    handle.join().unwrap();
+}
@@ -0,0 +1,173 @@
+use common_game::components::planet::PlanetType;
+use common_game::components::resource::{
+    BasicResourceType, ComplexResourceRequest, ComplexResourceType,
+};
+use common_game::protocols::orchestrator_planet::OrchestratorToPlanet;
+use common_game::protocols::planet_explorer::{ExplorerToPlanet, PlanetToExplorer};
+use crossbeam_channel::unbounded;
+use std::thread;
+use the_compiler_strikes_back::harness::PlanetHarness;
+use the_compiler_strikes_back::planet::{PlanetBuilder, create_planet};
+use the_compiler_strikes_back::strategy::Economic;
+use the_compiler_strikes_back::trace::{Direction, Replayable, TraceRecorder, load_trace, replay};
+
+//test for a recorded session replayed on an equivalent planet and on a different one
+#[test]
+fn test_trace_record_and_replay() {
+    let path = std::env::temp_dir().join("the_compiler_strikes_back_trace.jsonl");
+    let recorder = TraceRecorder::create(&path).unwrap();
+    let harness = PlanetHarness::spawn_with(
+        1,
+        PlanetBuilder::new(1)
+            .strategy(Box::new(Economic))
+            .trace(recorder),
+    )
+    .unwrap();
+    let explorer = harness.attach_explorer(101).unwrap();
+
+    harness.send_sunray().unwrap();
+    for resource in [BasicResourceType::Silicon, BasicResourceType::Oxygen] {
+        explorer
+            .request(ExplorerToPlanet::GenerateResourceRequest {
+                explorer_id: 101,
+                resource,
+            })
+            .unwrap();
+    }
+    harness.send_sunray().unwrap();
+    explorer
+        .request(ExplorerToPlanet::AvailableEnergyCellRequest { explorer_id: 101 })
+        .unwrap();
+    harness.send_asteroid().unwrap();
+    harness.detach_explorer(101).unwrap();
+    harness.kill().unwrap().unwrap();
+
+    let entries = load_trace(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(entries.len(), 20);
+    assert_eq!(entries[0].direction, Direction::FromOrchestrator);
+    assert!(
+        entries
+            .iter()
+            .filter(|e| e.direction == Direction::ToExplorer)
+            .all(|e| e.explorer_id == Some(101))
+    );
+
+    let report = replay(&entries, |rx_orch, tx_orch, rx_explorer| {
+        PlanetBuilder::new(1)
+            .strategy(Box::new(Economic))
+            .build(rx_orch, tx_orch, rx_explorer)
+            .unwrap()
+    });
+    assert!(report.is_clean(), "{:?}", report.diffs);
+    assert_eq!(report.replayed, 10);
+    assert_eq!(report.skipped, 0);
+
+    // a defensive planet turns the first charge into a rocket
+    let report = replay(&entries, |rx_orch, tx_orch, rx_explorer| {
+        create_planet(rx_orch, tx_orch, rx_explorer, 1)
+    });
+    assert!(!report.is_clean());
+}
+
+//test for a recorded combination, replayed with inputs made again by the replayer
+#[test]
+fn test_trace_replay_combination() {
+    let path = std::env::temp_dir().join("the_compiler_strikes_back_trace_combination.jsonl");
+    let builder = || {
+        PlanetBuilder::of_type(
+            1,
+            PlanetType::C,
+            vec![BasicResourceType::Carbon],
+            vec![ComplexResourceType::Diamond],
+        )
+        .strategy(Box::new(Economic))
+    };
+    let recorder = TraceRecorder::create(&path).unwrap();
+    let harness = PlanetHarness::spawn_with(1, builder().trace(recorder)).unwrap();
+    let explorer = harness.attach_explorer(101).unwrap();
+
+    let mut carbon = Vec::new();
+    for _ in 0..2 {
+        harness.send_sunray().unwrap();
+        match explorer
+            .request(ExplorerToPlanet::GenerateResourceRequest {
+                explorer_id: 101,
+                resource: BasicResourceType::Carbon,
+            })
+            .unwrap()
+        {
+            PlanetToExplorer::GenerateResourceResponse {
+                resource: Some(resource),
+            } => carbon.push(resource.to_carbon().unwrap()),
+            other => panic!("Unattended message {other:?}"),
+        }
+    }
+    harness.send_sunray().unwrap();
+    let (carbon2, carbon1) = (carbon.pop().unwrap(), carbon.pop().unwrap());
+    let response = explorer
+        .request(ExplorerToPlanet::CombineResourceRequest {
+            explorer_id: 101,
+            msg: ComplexResourceRequest::Diamond(carbon1, carbon2),
+        })
+        .unwrap();
+    assert!(matches!(
+        response,
+        PlanetToExplorer::CombineResourceResponse {
+            complex_response: Ok(_)
+        }
+    ));
+    harness.kill().unwrap().unwrap();
+
+    let entries = load_trace(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+    assert!(entries.windows(2).all(|pair| pair[0].seq < pair[1].seq));
+    assert!(entries.iter().any(|e| matches!(
+        &e.replay,
+        Some(Replayable::Combine { resource, inputs, .. })
+            if resource == "Diamond" && inputs == &["Carbon".to_string(), "Carbon".to_string()]
+    )));
+
+    let report = replay(&entries, |rx_orch, tx_orch, rx_explorer| {
+        builder().build(rx_orch, tx_orch, rx_explorer).unwrap()
+    });
+    assert!(report.is_clean(), "{:?}", report.diffs);
+    assert_eq!(report.skipped, 0);
+}
+
+//test for the recorder relays: they stop with the planet and release its channels
+#[test]
+fn test_trace_relays_stop() {
+    let path = std::env::temp_dir().join("the_compiler_strikes_back_trace_stop.jsonl");
+    let (tx_orch, rx_planet) = unbounded();
+    let (tx_planet, rx_orch) = unbounded();
+    let (tx_explorer, rx_explorer) = unbounded();
+    let mut planet = PlanetBuilder::new(1)
+        .trace(TraceRecorder::create(&path).unwrap())
+        .build(rx_planet, tx_planet, rx_explorer)
+        .unwrap();
+    let handle = thread::spawn(move || planet.run());
+
+    let (tx_local, rx_local) = unbounded();
+    for msg in [
+        OrchestratorToPlanet::StartPlanetAI,
+        OrchestratorToPlanet::IncomingExplorerRequest {
+            explorer_id: 101,
+            new_sender: tx_local,
+        },
+        OrchestratorToPlanet::KillPlanet,
+    ] {
+        tx_orch.send(msg).unwrap();
+        rx_orch.recv().unwrap();
+    }
+    handle.join().unwrap().unwrap();
+    let _ = std::fs::remove_file(&path);
+
+    assert!(tx_orch.send(OrchestratorToPlanet::StartPlanetAI).is_err());
+    assert!(
+        tx_explorer
+            .send(ExplorerToPlanet::AvailableEnergyCellRequest { explorer_id: 101 })
+            .is_err()
+    );
+    assert!(rx_local.recv().is_err());
+}